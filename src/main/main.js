@@ -100,6 +100,24 @@ app.on('window-all-closed', () => {
   }
 });
 
+// Script errors from executeCode carry their kind, position and stack trace.
+// line/column mark the start, endLine/endColumn the position just after the failing range.
+function scriptErrorDetails(error) {
+  if (!error.kind) {
+    return undefined;
+  }
+  return {
+    kind: error.kind,
+    code: error.code,
+    line: error.line,
+    column: error.column,
+    endLine: error.endLine,
+    endColumn: error.endColumn,
+    trace: error.trace,
+    diagnostics: error.diagnostics
+  };
+}
+
 // Set up IPC for communication with Rust native module
 ipcMain.handle('rust-function', async (event, functionName, ...args) => {
   try {
@@ -115,11 +133,119 @@ ipcMain.handle('rust-function', async (event, functionName, ...args) => {
     console.error('Error calling Rust function:', error);
     return { 
       success: false, 
-      error: error.message 
+      error: error.message,
+      details: scriptErrorDetails(error)
+    };
+  }
+});
+
+// executeCode needs an output callback, which can't cross IPC. Output goes to the
+// requesting window; options.id lets the renderer stop the run with cancelExecution.
+ipcMain.handle('execute-code', async (event, code, options) => {
+  try {
+    const sender = event.sender;
+    const result = await rustModule.executeCode(code, (output) => {
+      if (!sender.isDestroyed()) {
+        sender.send('console-output', output);
+      }
+    }, options);
+    return { success: true, result };
+  } catch (error) {
+    console.error('Error executing code:', error);
+    return {
+      success: false,
+      error: error.message,
+      details: scriptErrorDetails(error)
     };
   }
 });
 
+// Process event subscriptions, keyed by id so the renderer can unsubscribe
+const processSubscriptions = new Map();
+let nextProcessSubscriptionId = 1;
+
+ipcMain.handle('process-events-subscribe', (event, filter) => {
+  if (!rustModule.subscribeProcessEvents) {
+    return { success: false, error: 'Process events are not available' };
+  }
+
+  const id = nextProcessSubscriptionId++;
+  const sender = event.sender;
+  const subscription = rustModule.subscribeProcessEvents(filter, (processEvent) => {
+    if (!sender.isDestroyed()) {
+      sender.send('process-event', id, processEvent);
+    }
+  });
+  processSubscriptions.set(id, subscription);
+
+  // Drop the subscription together with the window that requested it
+  sender.once('destroyed', () => {
+    if (processSubscriptions.delete(id)) {
+      subscription.unsubscribe();
+    }
+  });
+  return { success: true, result: id };
+});
+
+ipcMain.handle('process-events-unsubscribe', (event, id) => {
+  const subscription = processSubscriptions.get(id);
+  if (!subscription) {
+    return { success: true, result: false };
+  }
+  processSubscriptions.delete(id);
+  return { success: true, result: subscription.unsubscribe() };
+});
+
+// Script sessions keep their globals between runs. JS functions can't cross IPC, so
+// main-process helpers are registered here as native functions of every new session.
+ipcMain.handle('session-create', (event, name) => {
+  try {
+    const sender = event.sender;
+    rustModule.createSession(name, (output) => {
+      if (!sender.isDestroyed()) {
+        sender.send('console-output', output);
+      }
+    });
+    rustModule.registerSessionFunction(name, 'sleep', (ms) => new Promise((resolve) => setTimeout(resolve, ms)), {
+      params: ['ms'],
+      doc: 'Waits for the given number of milliseconds'
+    });
+
+    // Drop the session together with the window that created it
+    sender.once('destroyed', () => rustModule.dropSession(name));
+    return { success: true, result: name };
+  } catch (error) {
+    console.error('Error creating script session:', error);
+    return { success: false, error: error.message };
+  }
+});
+
+// Archived history can't cross IPC as an object, so load the window and answer the query here.
+// request: { name } lists processes, { pid, query } returns one process, { query } the system series.
+// loadHistory reads the files on a worker thread and resolves to the archive.
+ipcMain.handle('history-archive-query', async (event, directory, range = {}, request = {}) => {
+  try {
+    const archive = await rustModule.loadHistory(directory, range.from, range.to);
+    // Damaged files don't fail the query; the rest of the archive is still usable
+    const skipped = archive.skippedFiles();
+    if (skipped.length > 0) {
+      console.warn('Skipped unreadable history files:', skipped);
+    }
+    let result;
+    if (request.pid !== undefined) {
+      result = archive.processHistory(request.pid, request.query);
+    } else if (request.name !== undefined) {
+      result = archive.processes(request.name);
+    } else {
+      result = archive.systemHistory(request.query);
+    }
+    return { success: true, result };
+  } catch (error) {
+    console.error('Error querying history archive:', error);
+    return { success: false, error: error.message };
+  }
+});
+
 // Handle navigation requests
 ipcMain.on('navigate', (event, page) => {
   currentPage = page;
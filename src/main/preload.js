@@ -17,8 +17,54 @@ contextBridge.exposeInMainWorld('electron', {
     getAllProcesses: () => ipcRenderer.invoke('rust-function', 'getAllProcesses'),
     getSystemInfo: () => ipcRenderer.invoke('rust-function', 'getSystemInfo'),
     
+    // Background sampling of process and system metrics
+    startSampler: (options) => ipcRenderer.invoke('rust-function', 'startSampler', options),
+    stopSampler: () => ipcRenderer.invoke('rust-function', 'stopSampler'),
+    configureSampler: (options) => ipcRenderer.invoke('rust-function', 'configureSampler', options),
+    getSamplerStatus: () => ipcRenderer.invoke('rust-function', 'getSamplerStatus'),
+    
+    // Sampled CPU and memory history with min/max/avg per bucket
+    getProcessHistory: (pid, query) => ipcRenderer.invoke('rust-function', 'getProcessHistory', pid, query),
+    getSystemHistory: (query) => ipcRenderer.invoke('rust-function', 'getSystemHistory', query),
+    configureHistory: (options) => ipcRenderer.invoke('rust-function', 'configureHistory', options),
+    
+    // History files on disk and queries over a stored time window
+    startHistoryPersistence: (options) => ipcRenderer.invoke('rust-function', 'startHistoryPersistence', options),
+    stopHistoryPersistence: () => ipcRenderer.invoke('rust-function', 'stopHistoryPersistence'),
+    getHistoryPersistenceStatus: () => ipcRenderer.invoke('rust-function', 'getHistoryPersistenceStatus'),
+    queryHistoryArchive: (directory, range, request) => ipcRenderer.invoke('history-archive-query', directory, range, request),
+    
+    // Push-based process events (started, exited, changed).
+    // Resolves to a function that ends the subscription.
+    subscribeProcessEvents: async (filter, callback) => {
+      const response = await ipcRenderer.invoke('process-events-subscribe', filter);
+      if (!response.success) {
+        throw new Error(response.error);
+      }
+
+      const id = response.result;
+      const listener = (_, eventId, processEvent) => {
+        if (eventId === id) callback(processEvent);
+      };
+      ipcRenderer.on('process-event', listener);
+      return () => {
+        ipcRenderer.removeListener('process-event', listener);
+        return ipcRenderer.invoke('process-events-unsubscribe', id);
+      };
+    },
+    
     // Code execution
-    executeCode: (code) => ipcRenderer.invoke('rust-function', 'executeCode', code),
+    // options: { id, maxSteps, timeoutMs, maxCallDepth, globals }; a run with an id can be cancelled
+    executeCode: (code, options) => ipcRenderer.invoke('execute-code', code, options),
+    cancelExecution: (id) => ipcRenderer.invoke('rust-function', 'cancelExecution', id),
+    // With a session name the list includes the functions registered in that session
+    getBuiltinSignatures: (session) => ipcRenderer.invoke('rust-function', 'getBuiltinSignatures', session),
+    
+    // Script sessions keep globals between runs; output arrives via onConsoleOutput
+    createSession: (name) => ipcRenderer.invoke('session-create', name),
+    runInSession: (name, code, options) => ipcRenderer.invoke('rust-function', 'runInSession', name, code, options),
+    getSessionGlobals: (name) => ipcRenderer.invoke('rust-function', 'getSessionGlobals', name),
+    dropSession: (name) => ipcRenderer.invoke('rust-function', 'dropSession', name),
     
     // Console output handling
     onConsoleOutput: (callback) => {
@@ -107,15 +107,35 @@ const ProcessScanner = ({ onProcessSelect, onProcessUpdate, onProcessNotFound })
     }
   }, [selectedProcess, onProcessUpdate, onProcessNotFound, initialLoadComplete]);
 
-  // Initialer Prozessabruf und Intervall einrichten
+  // Die aktuelle Funktion für den Event-Listener, ohne die Anmeldung bei jeder Änderung zu erneuern
+  const fetchProcessesRef = useRef(fetchProcesses);
+  fetchProcessesRef.current = fetchProcesses;
+
+  // Initialer Prozessabruf, danach Aktualisierung bei Prozess-Events statt per Intervall
   useEffect(() => {
-    fetchProcesses(true); // Beim ersten Aufruf als manuelle Aktualisierung behandeln
-    
-    // Aktualisiere die Prozessliste jede Sekunde
-    const intervalId = setInterval(() => fetchProcesses(false), 1000);
-    
-    return () => clearInterval(intervalId);
-  }, [fetchProcesses]);
+    fetchProcessesRef.current(true); // Beim ersten Aufruf als manuelle Aktualisierung behandeln
+
+    const rustAPI = window.electron && window.electron.rustAPI;
+    if (!rustAPI || !rustAPI.subscribeProcessEvents) return undefined;
+
+    let unsubscribe = null;
+    let cancelled = false;
+    rustAPI.subscribeProcessEvents(
+      { name: 'aion.bin', cpuThreshold: 1, memoryThreshold: 1024 * 1024 },
+      () => fetchProcessesRef.current(false)
+    ).then((stop) => {
+      if (cancelled) {
+        stop();
+      } else {
+        unsubscribe = stop;
+      }
+    }).catch((err) => console.error('Prozess-Events nicht verfügbar:', err));
+
+    return () => {
+      cancelled = true;
+      if (unsubscribe) unsubscribe();
+    };
+  }, []);
 
   // Handle process selection and navigation
   const handleProcessClick = (process) => {
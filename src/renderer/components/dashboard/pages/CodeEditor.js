@@ -5,6 +5,79 @@ import 'prismjs/components/prism-javascript';
 import '../../../styles/components/dashboard/pages/page.css';
 import '../../../styles/components/dashboard/pages/code-editor.css';
 
+// Zeichenposition für Zeile/Spalte aus dem Interpreter (beide ab 1)
+const offsetOf = (content, line, column) => {
+  const lines = content.split('\n');
+  let offset = 0;
+  for (let i = 0; i < line - 1 && i < lines.length; i++) {
+    offset += lines[i].length + 1;
+  }
+  return Math.min(offset + Math.max(column - 1, 0), content.length);
+};
+
+// Fehlerbereiche aus den Details eines fehlgeschlagenen Laufs. Syntaxfehler liefern alle
+// Diagnosen, Laufzeitfehler einen Bereich; endLine/endColumn liegen direkt dahinter.
+const errorRangesOf = (details) => {
+  if (!details) return [];
+  const spans = details.diagnostics && details.diagnostics.length ? details.diagnostics : [details];
+  return spans
+    .filter(span => span.line !== undefined && span.column !== undefined)
+    .map(span => ({
+      line: span.line,
+      column: span.column,
+      endLine: span.endLine ?? span.line,
+      endColumn: span.endColumn ?? span.column + 1
+    }));
+};
+
+// Wort direkt vor dem Cursor, Grundlage für die Vervollständigung
+const wordBefore = (content, cursor) => {
+  const match = content.slice(0, cursor).match(/[A-Za-z_][A-Za-z0-9_]*$/);
+  return match ? match[0] : '';
+};
+
+// Offener Aufruf um den Cursor: Funktionsname und Index des aktuellen Arguments.
+// Sucht rückwärts bis zur ersten nicht geschlossenen Klammer, höchstens bis zum Anweisungsende.
+const callAt = (content, cursor) => {
+  let depth = 0;
+  let argument = 0;
+  for (let i = cursor - 1; i >= 0; i--) {
+    const char = content[i];
+    if (char === ')' || char === ']') {
+      depth++;
+    } else if (char === '[') {
+      depth--;
+    } else if (char === '(') {
+      if (depth === 0) {
+        const name = wordBefore(content, i);
+        return name ? { name, argument } : null;
+      }
+      depth--;
+    } else if (char === ',' && depth === 0) {
+      argument++;
+    } else if ((char === ';' || char === '{' || char === '}') && depth === 0) {
+      return null;
+    }
+  }
+  return null;
+};
+
+// Parameter wie in der Signatur aus Rust: name: typ, name?: typ, ...name: typ
+const formatParam = (param) => {
+  if (param.variadic) return `...${param.name}: ${param.type}`;
+  return `${param.name}${param.optional ? '?' : ''}: ${param.type}`;
+};
+
+// Aktiver Parameter; variadische Parameter nehmen alle restlichen Argumente auf
+const activeParamIndex = (params, argument) => {
+  if (argument < params.length) return argument;
+  const last = params.length - 1;
+  return last >= 0 && params[last].variadic ? last : -1;
+};
+
+// Höchstens so viele Vorschläge werden angezeigt
+const MAX_COMPLETIONS = 5;
+
 const CodeEditor = () => {
   const [content, setContent] = useState('');
   const [lineCount, setLineCount] = useState(1);
@@ -15,6 +88,11 @@ const CodeEditor = () => {
   const codeInputRef = useRef(null);
   const highlightingRef = useRef(null);
   const [highlightedCode, setHighlightedCode] = useState('');
+  const [errorRanges, setErrorRanges] = useState([]);
+  const errorLayerRef = useRef(null);
+  const [signatures, setSignatures] = useState([]);
+  const [cursor, setCursor] = useState(0);
+  const sessionRef = useRef(null);
 
   // Füge useEffect für Console Output Listener hinzu
   useEffect(() => {
@@ -31,6 +109,40 @@ const CodeEditor = () => {
     }
   }, []);
 
+  // Eigene Session des Editors: globale Variablen bleiben zwischen den Läufen erhalten, und
+  // Vervollständigung und Signaturhilfe kennen auch die dort registrierten Funktionen
+  useEffect(() => {
+    if (!window.electron || !window.electron.rustAPI) return undefined;
+    const rustAPI = window.electron.rustAPI;
+    const name = `code-editor-${crypto.randomUUID()}`;
+    let mounted = true;
+
+    rustAPI.createSession(name)
+      .then(result => {
+        if (!result.success) throw new Error(result.error);
+        if (!mounted) {
+          rustAPI.dropSession(name);
+          return null;
+        }
+        sessionRef.current = name;
+        return rustAPI.getBuiltinSignatures(name);
+      })
+      .then(result => {
+        if (result && result.success && mounted) {
+          setSignatures(JSON.parse(result.result));
+        }
+      })
+      .catch(error => console.error('Error creating editor session:', error));
+
+    return () => {
+      mounted = false;
+      if (sessionRef.current) {
+        rustAPI.dropSession(sessionRef.current);
+        sessionRef.current = null;
+      }
+    };
+  }, []);
+
   // Aktualisiere die Zeilennummern und Syntax Highlighting wenn sich der Content ändert
   useEffect(() => {
     const lines = content.split('\n').length;
@@ -57,12 +169,23 @@ const CodeEditor = () => {
             if (highlightingRef.current) {
               highlightingRef.current.scrollTop = newScrollTop;
             }
+            if (errorLayerRef.current) {
+              errorLayerRef.current.scrollTop = newScrollTop;
+            }
           }
         });
       }
     }
   }, [content]);
 
+  // Neu eingeblendete Fehlermarkierungen an die aktuelle Scroll-Position anpassen
+  useEffect(() => {
+    if (errorLayerRef.current && codeInputRef.current) {
+      errorLayerRef.current.scrollTop = codeInputRef.current.scrollTop;
+      errorLayerRef.current.scrollLeft = codeInputRef.current.scrollLeft;
+    }
+  }, [errorRanges]);
+
   // Synchronisiere Scroll-Position zwischen allen Elementen
   const handleScroll = (e) => {
     const scrollTop = e.target.scrollTop;
@@ -75,11 +198,48 @@ const CodeEditor = () => {
       highlightingRef.current.scrollTop = scrollTop;
       highlightingRef.current.scrollLeft = scrollLeft;
     }
+    if (errorLayerRef.current) {
+      errorLayerRef.current.scrollTop = scrollTop;
+      errorLayerRef.current.scrollLeft = scrollLeft;
+    }
   };
 
   // Event Handler
   const handleContentChange = (e) => {
     setContent(e.target.value);
+    setCursor(e.target.selectionStart);
+    // Die Markierungen gehören zum ausgeführten Stand
+    setErrorRanges([]);
+  };
+
+  const handleCursorMove = (e) => {
+    setCursor(e.target.selectionStart);
+  };
+
+  // Vorschläge für das Wort vor dem Cursor, ohne das bereits vollständige Wort selbst
+  const prefix = wordBefore(content, cursor);
+  const completions = prefix
+    ? signatures.filter(sig => sig.name.startsWith(prefix) && sig.name !== prefix).slice(0, MAX_COMPLETIONS)
+    : [];
+  const call = callAt(content, cursor);
+  const callSignature = call ? signatures.find(sig => sig.name === call.name) : null;
+
+  // Tab übernimmt den ersten Vorschlag
+  const handleKeyDown = (e) => {
+    if (e.key !== 'Tab' || completions.length === 0) return;
+    e.preventDefault();
+
+    const rest = completions[0].name.slice(prefix.length);
+    const position = cursor + rest.length;
+    setContent(content.slice(0, cursor) + rest + content.slice(cursor));
+    setCursor(position);
+    setErrorRanges([]);
+    requestAnimationFrame(() => {
+      if (codeInputRef.current) {
+        codeInputRef.current.selectionStart = position;
+        codeInputRef.current.selectionEnd = position;
+      }
+    });
   };
 
   const handleLoad = async () => {
@@ -120,13 +280,19 @@ const CodeEditor = () => {
       setIsExecuting(true);
       // Leere die Konsole vor der Ausführung
       setConsoleContent([]);
+      setErrorRanges([]);
 
       if (window.electron && window.electron.rustAPI) {
-        const result = await window.electron.rustAPI.executeCode(content);
+        // Ohne Session (z.B. wenn sie nicht angelegt werden konnte) läuft das Skript für sich
+        const session = sessionRef.current;
+        const result = session
+          ? await window.electron.rustAPI.runInSession(session, content)
+          : await window.electron.rustAPI.executeCode(content);
         
         if (result.error) {
-          // Zeige Fehler in der Konsole an
+          // Zeige Fehler in der Konsole an und markiere die Stelle im Code
           setConsoleContent(prev => [...prev, `Error: ${result.error}`]);
+          setErrorRanges(errorRangesOf(result.details));
         }
       }
     } catch (error) {
@@ -137,6 +303,30 @@ const CodeEditor = () => {
     }
   };
 
+  // Gleicher Text wie in der Eingabe, aber unsichtbar; nur die Fehlerbereiche werden unterstrichen
+  const renderErrorLayer = () => {
+    const ranges = errorRanges
+      .map(range => {
+        const start = offsetOf(content, range.line, range.column);
+        // Leere Bereiche (z.B. am Dateiende) wenigstens ein Zeichen breit markieren
+        const end = Math.max(offsetOf(content, range.endLine, range.endColumn), start + 1);
+        return { start, end: Math.min(end, content.length) };
+      })
+      .sort((a, b) => a.start - b.start);
+
+    const parts = [];
+    let position = 0;
+    ranges.forEach((range, index) => {
+      const start = Math.max(range.start, position);
+      if (start >= range.end) return;
+      parts.push(content.slice(position, start));
+      parts.push(<span key={index} className="code-error">{content.slice(start, range.end)}</span>);
+      position = range.end;
+    });
+    parts.push(content.slice(position));
+    return parts;
+  };
+
   return (
     <div className="dashboard-page-content">
       <h1 className="page-title">
@@ -178,6 +368,9 @@ const CodeEditor = () => {
                 className="code-input"
                 value={content}
                 onChange={handleContentChange}
+                onKeyDown={handleKeyDown}
+                onKeyUp={handleCursorMove}
+                onClick={handleCursorMove}
                 onScroll={handleScroll}
                 spellCheck="false"
                 autoCapitalize="none"
@@ -189,8 +382,40 @@ const CodeEditor = () => {
                 className="code-highlighting"
                 dangerouslySetInnerHTML={{ __html: highlightedCode }}
               />
+              {errorRanges.length > 0 && (
+                <div ref={errorLayerRef} className="code-errors" aria-hidden="true">
+                  {renderErrorLayer()}
+                </div>
+              )}
             </div>
           </div>
+
+          {completions.length > 0 ? (
+            <div className="code-hint">
+              {completions.map((sig, index) => (
+                <span key={sig.name} className={index === 0 ? 'code-completion selected' : 'code-completion'}>
+                  {sig.name}
+                </span>
+              ))}
+              <span className="code-hint-doc">Tab to complete</span>
+            </div>
+          ) : callSignature && (
+            <div className="code-hint">
+              <span className="code-signature">
+                {callSignature.name}(
+                {callSignature.params.map((param, index) => (
+                  <React.Fragment key={param.name}>
+                    {index > 0 && ', '}
+                    <span className={index === activeParamIndex(callSignature.params, call.argument) ? 'code-param active' : 'code-param'}>
+                      {formatParam(param)}
+                    </span>
+                  </React.Fragment>
+                ))}
+                ): {callSignature.returns}
+              </span>
+              {callSignature.doc && <span className="code-hint-doc">{callSignature.doc}</span>}
+            </div>
+          )}
           
           <div className="console-section">
             <div className="console-header">
@@ -3,9 +3,119 @@
 
 /* auto-generated by NAPI-RS */
 
-export declare function findProcess(processName: string): NapiResult
-export declare function getProcessInfo(pid: number): NapiResult
+export interface ExecutionOptions {
+  id?: string
+  maxSteps?: number
+  timeoutMs?: number
+  maxCallDepth?: number
+  globals?: object
+}
+export declare function executeCode(code: string, callback: (arg: string) => any, options?: ExecutionOptions | undefined | null): Promise<unknown>
+export declare function cancelExecution(id: string): boolean
+export declare function createSession(name: string, callback: (arg: string) => any): void
+export declare function runInSession(name: string, code: string, options?: ExecutionOptions | undefined | null): Promise<unknown>
+export declare function getSessionGlobals(name: string): object
+export interface ScriptFunctionOptions {
+  params?: Array<string>
+  doc?: string
+}
+export declare function registerSessionFunction(session: string, name: string, callback: (...args: any[]) => any, options?: ScriptFunctionOptions | undefined | null): void
+export declare function dropSession(name: string): boolean
+export declare function getBuiltinSignatures(session?: string | undefined | null): string
+export interface SamplerOptions {
+  intervalMs?: number
+}
+export interface SamplerStatus {
+  running: boolean
+  users: number
+  intervalMs: number
+  lastSample?: number
+}
+export declare function startSampler(options?: SamplerOptions | undefined | null): boolean
+export declare function stopSampler(): boolean
+export declare function configureSampler(options: SamplerOptions): void
+export declare function getSamplerStatus(): SamplerStatus
+export interface ProcessEventFilter {
+  name?: string
+  pid?: number
+  cpuThreshold?: number
+  memoryThreshold?: number
+  includeExisting?: boolean
+}
+export interface ProcessEventInfo {
+  pid: number
+  name: string
+  memory_usage_kb: number
+  cpu_usage_percent: number
+}
+export interface ProcessEventPayload {
+  kind: string
+  timestamp: number
+  process: ProcessEventInfo
+  previous?: ProcessEventInfo
+}
+export declare function subscribeProcessEvents(filter: ProcessEventFilter | undefined | null, callback: (...args: any[]) => any): ProcessSubscription
+export interface HistoryQuery {
+  from?: number
+  to?: number
+  bucketMs?: number
+}
+export interface HistoryBucket {
+  start: number
+  end: number
+  samples: number
+  cpuMin: number
+  cpuMax: number
+  cpuAvg: number
+  memoryMin: number
+  memoryMax: number
+  memoryAvg: number
+}
+export interface ProcessHistory {
+  pid: number
+  name: string
+  buckets: Array<HistoryBucket>
+}
+export interface HistoryOptions {
+  capacity?: number
+}
+export declare function getProcessHistory(pid: number, query?: HistoryQuery | undefined | null): ProcessHistory | null
+export declare function getSystemHistory(query?: HistoryQuery | undefined | null): Array<HistoryBucket>
+export declare function configureHistory(options: HistoryOptions): void
+export interface HistoryPersistenceOptions {
+  directory: string
+  maxFileBytes?: number
+  maxTotalBytes?: number
+  maxAgeMs?: number
+  processFilter?: string
+}
+export interface HistoryPersistenceStatus {
+  active: boolean
+  directory?: string
+  lastError?: string
+}
+export declare function startHistoryPersistence(options: HistoryPersistenceOptions): void
+export declare function stopHistoryPersistence(): boolean
+export declare function getHistoryPersistenceStatus(): HistoryPersistenceStatus
+export interface ArchivedProcess {
+  pid: number
+  name: string
+  first: number
+  last: number
+}
+export declare function loadHistory(directory: string, from?: number | undefined | null, to?: number | undefined | null): Promise<unknown>
+export declare function findProcess(processName: string): string
+export declare function getProcessInfo(pid: number): string
 export declare function getAllProcesses(): string
 export declare function searchProcesses(namePattern: string): string
 export declare function getSystemInfo(): string
 export declare function addNumbers(a: number, b: number): number
+export class ProcessSubscription {
+  unsubscribe(): boolean
+}
+export class HistoryArchive {
+  processes(name?: string | undefined | null): Array<ArchivedProcess>
+  processHistory(pid: number, query?: HistoryQuery | undefined | null): ProcessHistory | null
+  systemHistory(query?: HistoryQuery | undefined | null): Array<HistoryBucket>
+  skippedFiles(): Array<string>
+}
@@ -310,8 +310,30 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { findProcess, getProcessInfo, getAllProcesses, searchProcesses, getSystemInfo, addNumbers } = nativeBinding
+const { executeCode, cancelExecution, createSession, runInSession, getSessionGlobals, registerSessionFunction, dropSession, getBuiltinSignatures, startSampler, stopSampler, configureSampler, getSamplerStatus, ProcessSubscription, subscribeProcessEvents, getProcessHistory, getSystemHistory, configureHistory, startHistoryPersistence, stopHistoryPersistence, getHistoryPersistenceStatus, HistoryArchive, loadHistory, findProcess, getProcessInfo, getAllProcesses, searchProcesses, getSystemInfo, addNumbers } = nativeBinding
 
+module.exports.executeCode = executeCode
+module.exports.cancelExecution = cancelExecution
+module.exports.createSession = createSession
+module.exports.runInSession = runInSession
+module.exports.getSessionGlobals = getSessionGlobals
+module.exports.registerSessionFunction = registerSessionFunction
+module.exports.dropSession = dropSession
+module.exports.getBuiltinSignatures = getBuiltinSignatures
+module.exports.startSampler = startSampler
+module.exports.stopSampler = stopSampler
+module.exports.configureSampler = configureSampler
+module.exports.getSamplerStatus = getSamplerStatus
+module.exports.ProcessSubscription = ProcessSubscription
+module.exports.subscribeProcessEvents = subscribeProcessEvents
+module.exports.getProcessHistory = getProcessHistory
+module.exports.getSystemHistory = getSystemHistory
+module.exports.configureHistory = configureHistory
+module.exports.startHistoryPersistence = startHistoryPersistence
+module.exports.stopHistoryPersistence = stopHistoryPersistence
+module.exports.getHistoryPersistenceStatus = getHistoryPersistenceStatus
+module.exports.HistoryArchive = HistoryArchive
+module.exports.loadHistory = loadHistory
 module.exports.findProcess = findProcess
 module.exports.getProcessInfo = getProcessInfo
 module.exports.getAllProcesses = getAllProcesses
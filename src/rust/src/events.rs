@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, Error, JsFunction, Status};
+
+use crate::monitor::{monitor, ProcessSample, Snapshot};
+use crate::sampler::sampler;
+
+// Welche Prozesse beobachtet werden und ab welcher Änderung ein `Changed` gemeldet wird
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub name: Option<String>,  // Teil des Namens, ohne Beachtung der Groß-/Kleinschreibung
+    pub pid: Option<u32>,
+    pub cpu_threshold: Option<f32>,  // Prozentpunkte
+    pub memory_threshold: Option<u64>,  // Bytes
+    pub include_existing: bool,  // Bereits laufende Prozesse zu Beginn als gestartet melden
+}
+
+impl EventFilter {
+    fn matches(&self, process: &ProcessSample) -> bool {
+        self.pid.is_none_or(|pid| pid == process.pid)
+            && self.name.as_ref().is_none_or(|name| process.name.to_lowercase().contains(&name.to_lowercase()))
+    }
+
+    fn changed(&self, reported: &ProcessSample, current: &ProcessSample) -> bool {
+        self.cpu_threshold.is_some_and(|threshold| (current.cpu_usage - reported.cpu_usage).abs() >= threshold)
+            || self.memory_threshold.is_some_and(|threshold| current.memory.abs_diff(reported.memory) >= threshold)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessEvent {
+    Started(ProcessSample),
+    Exited(ProcessSample),
+    Changed { process: ProcessSample, previous: ProcessSample },
+}
+
+// Vergleicht aufeinanderfolgende Aufnahmen. Für `Changed` zählt der Abstand zum zuletzt
+// gemeldeten Wert, damit auch langsames Wachstum irgendwann gemeldet wird.
+pub struct EventTracker {
+    filter: EventFilter,
+    reported: HashMap<u32, ProcessSample>,
+    initialized: bool,
+}
+
+impl EventTracker {
+    pub fn new(filter: EventFilter) -> Self {
+        EventTracker {
+            filter,
+            reported: HashMap::new(),
+            initialized: false,
+        }
+    }
+
+    pub fn update(&mut self, snapshot: &Snapshot) -> Vec<ProcessEvent> {
+        let mut events = Vec::new();
+        let mut current = HashMap::new();
+
+        for process in snapshot.system.processes.iter().filter(|process| self.filter.matches(process)) {
+            match self.reported.get(&process.pid) {
+                // Gleiche PID, anderer Name: Die PID wurde neu vergeben
+                Some(reported) if reported.name != process.name => {
+                    events.push(ProcessEvent::Exited(reported.clone()));
+                    events.push(ProcessEvent::Started(process.clone()));
+                    current.insert(process.pid, process.clone());
+                },
+                Some(reported) if self.filter.changed(reported, process) => {
+                    events.push(ProcessEvent::Changed { process: process.clone(), previous: reported.clone() });
+                    current.insert(process.pid, process.clone());
+                },
+                Some(reported) => {
+                    current.insert(process.pid, reported.clone());
+                },
+                None => {
+                    if self.initialized || self.filter.include_existing {
+                        events.push(ProcessEvent::Started(process.clone()));
+                    }
+                    current.insert(process.pid, process.clone());
+                },
+            }
+        }
+
+        let mut exited: Vec<&ProcessSample> = self.reported
+            .iter()
+            .filter(|(pid, _)| !current.contains_key(pid))
+            .map(|(_, process)| process)
+            .collect();
+        exited.sort_by_key(|process| process.pid);
+        events.extend(exited.into_iter().cloned().map(ProcessEvent::Exited));
+
+        self.reported = current;
+        self.initialized = true;
+        events
+    }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ProcessEventFilter {
+    pub name: Option<String>,
+    pub pid: Option<u32>,
+    pub cpu_threshold: Option<f64>,
+    pub memory_threshold: Option<f64>,  // Bytes
+    pub include_existing: Option<bool>,
+}
+
+// Gleiche Feldnamen wie `ProcessInfo` aus `lib.rs`, damit die UI beide gleich behandeln kann
+#[napi(object)]
+pub struct ProcessEventInfo {
+    pub pid: u32,
+    pub name: String,
+    #[napi(js_name = "memory_usage_kb")]
+    pub memory_usage_kb: f64,
+    #[napi(js_name = "cpu_usage_percent")]
+    pub cpu_usage_percent: f64,
+}
+
+impl From<ProcessSample> for ProcessEventInfo {
+    fn from(sample: ProcessSample) -> Self {
+        ProcessEventInfo {
+            pid: sample.pid,
+            name: sample.name,
+            memory_usage_kb: sample.memory as f64,
+            cpu_usage_percent: sample.cpu_usage as f64,
+        }
+    }
+}
+
+// `kind` ist "started", "exited" oder "changed"; `previous` nur bei "changed"
+#[napi(object)]
+pub struct ProcessEventPayload {
+    pub kind: String,
+    pub timestamp: f64,
+    pub process: ProcessEventInfo,
+    pub previous: Option<ProcessEventInfo>,
+}
+
+impl ProcessEventPayload {
+    fn new(event: ProcessEvent, timestamp: u64) -> Self {
+        let (kind, process, previous) = match event {
+            ProcessEvent::Started(process) => ("started", process, None),
+            ProcessEvent::Exited(process) => ("exited", process, None),
+            ProcessEvent::Changed { process, previous } => ("changed", process, Some(previous.into())),
+        };
+        ProcessEventPayload {
+            kind: kind.to_string(),
+            timestamp: timestamp as f64,
+            process: process.into(),
+            previous,
+        }
+    }
+}
+
+type EventCallback = ThreadsafeFunction<ProcessEventPayload, ErrorStrategy::Fatal>;
+
+// Handle einer Anmeldung; `unsubscribe` beendet die Benachrichtigungen. Räumt der
+// Garbage Collector ein vergessenes Handle ab, wird ebenfalls abgemeldet.
+#[napi]
+pub struct ProcessSubscription {
+    observer: u64,
+    callback: Mutex<Option<EventCallback>>,
+}
+
+#[napi]
+impl ProcessSubscription {
+    // false, wenn bereits abgemeldet. Gibt den Sampler frei, den die Anmeldung gestartet hat.
+    #[napi]
+    pub fn unsubscribe(&self) -> bool {
+        // Auch ein Aufruf, den eine gerade laufende Aufnahme noch absetzt, erreicht JS nicht mehr
+        if let Some(callback) = self.callback.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            let _ = callback.abort();
+        }
+        if !monitor().unobserve(self.observer) {
+            return false;
+        }
+        sampler().release();
+        true
+    }
+}
+
+impl Drop for ProcessSubscription {
+    fn drop(&mut self) {
+        self.unsubscribe();
+    }
+}
+
+// Meldet Start, Ende und Änderungen passender Prozesse an `callback`. Grundlage sind
+// die Aufnahmen des Samplers, der dafür bis zum `unsubscribe` in Betrieb bleibt.
+#[napi]
+pub fn subscribe_process_events(
+    env: Env,
+    filter: Option<ProcessEventFilter>,
+    callback: JsFunction,
+) -> napi::Result<ProcessSubscription> {
+    let filter = filter.unwrap_or_default();
+    // Bei 0 oder weniger wäre jede Aufnahme eine Änderung
+    for (field, threshold) in [("cpuThreshold", filter.cpu_threshold), ("memoryThreshold", filter.memory_threshold)] {
+        if threshold.is_some_and(|threshold| threshold.is_nan() || threshold <= 0.0) {
+            return Err(Error::new(Status::InvalidArg, format!("{} must be greater than 0", field)));
+        }
+    }
+
+    let mut tracker = EventTracker::new(EventFilter {
+        name: filter.name,
+        pid: filter.pid,
+        cpu_threshold: filter.cpu_threshold.map(|threshold| threshold as f32),
+        memory_threshold: filter.memory_threshold.map(|threshold| threshold as u64),
+        include_existing: filter.include_existing.unwrap_or(false),
+    });
+
+    let mut tsfn: EventCallback = callback
+        .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<ProcessEventPayload>| Ok(vec![ctx.value]))?;
+    // Eine vergessene Anmeldung soll Node nicht am Beenden hindern
+    tsfn.unref(&env)?;
+
+    let subscription_callback = tsfn.clone();
+    let observer = monitor().observe(Box::new(move |snapshot: &Snapshot| {
+        for event in tracker.update(snapshot) {
+            tsfn.call(ProcessEventPayload::new(event, snapshot.timestamp), ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }));
+    sampler().acquire();
+
+    Ok(ProcessSubscription { observer, callback: Mutex::new(Some(subscription_callback)) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::fixtures::{process, snapshot};
+    use crate::sampler::TEST_SERIAL;
+
+    #[test]
+    fn test_started_exited_and_changed() {
+        let mut tracker = EventTracker::new(EventFilter {
+            name: Some("AION".to_string()),
+            memory_threshold: Some(100),
+            ..Default::default()
+        });
+
+        // Die erste Aufnahme legt nur den Ausgangszustand fest
+        assert_eq!(tracker.update(&snapshot(0, vec![process(1, "aion.bin", 1000, 1.0), process(2, "bash", 0, 0.0)])), vec![]);
+        assert_eq!(
+            tracker.update(&snapshot(0, vec![process(1, "aion.bin", 1050, 1.0), process(3, "aion.bin", 500, 0.0)])),
+            vec![ProcessEvent::Started(process(3, "aion.bin", 500, 0.0))]
+        );
+        // 1000 -> 1050 -> 1120: Verglichen wird mit dem zuletzt gemeldeten Wert
+        assert_eq!(
+            tracker.update(&snapshot(0, vec![process(1, "aion.bin", 1120, 1.0), process(3, "aion.bin", 500, 0.0)])),
+            vec![ProcessEvent::Changed {
+                process: process(1, "aion.bin", 1120, 1.0),
+                previous: process(1, "aion.bin", 1000, 1.0),
+            }]
+        );
+        assert_eq!(
+            tracker.update(&snapshot(0, vec![process(3, "aion.bin", 500, 0.0)])),
+            vec![ProcessEvent::Exited(process(1, "aion.bin", 1120, 1.0))]
+        );
+    }
+
+    #[test]
+    fn test_include_existing_and_reused_pid() {
+        let mut tracker = EventTracker::new(EventFilter { include_existing: true, ..Default::default() });
+        assert_eq!(
+            tracker.update(&snapshot(0, vec![process(7, "old", 0, 0.0)])),
+            vec![ProcessEvent::Started(process(7, "old", 0, 0.0))]
+        );
+        assert_eq!(
+            tracker.update(&snapshot(0, vec![process(7, "new", 0, 0.0)])),
+            vec![ProcessEvent::Exited(process(7, "old", 0, 0.0)), ProcessEvent::Started(process(7, "new", 0, 0.0))]
+        );
+    }
+
+    #[test]
+    fn test_dropped_subscription_unsubscribes() {
+        let _serial = TEST_SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+        let users = sampler().users();
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let observer = monitor().observe(Box::new(move |_: &Snapshot| {
+            let _ = &sender;
+        }));
+        sampler().acquire();
+        let subscription = ProcessSubscription { observer, callback: Mutex::new(None) };
+        assert_eq!(sampler().users(), users + 1);
+
+        drop(subscription);
+        assert_eq!(sampler().users(), users);
+        assert!(!monitor().unobserve(observer));
+        // Der Beobachter samt allem, was er hält, ist freigegeben
+        assert!(receiver.recv().is_err());
+    }
+}
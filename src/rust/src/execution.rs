@@ -0,0 +1,374 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::Duration;
+use napi::bindgen_prelude::AsyncTask;
+use napi::threadsafe_function::{ThreadsafeFunction, ErrorStrategy};
+use napi::{
+    Env, Error, JsBoolean, JsNumber, JsObject, JsString, JsUnknown, Result as NapiResult, Status, Task, ValueType,
+};
+use once_cell::sync::Lazy;
+
+use crate::process_builtins::register_process_builtins;
+use crate::language::{
+    Backend, CancellationToken, ExecutionLimits, Interpreter, Parser, ParserError, RuntimeError, ScriptError, Span, Value,
+};
+
+// Laufende Skripte mit Kennung, damit `cancel_execution` sie abbrechen kann
+static RUNNING: Lazy<Mutex<HashMap<String, CancellationToken>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Optionen für `executeCode`; alle Felder sind optional
+#[napi(object)]
+#[derive(Default)]
+pub struct ExecutionOptions {
+    pub id: Option<String>,
+    pub max_steps: Option<i64>,
+    pub timeout_ms: Option<u32>,
+    pub max_call_depth: Option<u32>,
+    pub globals: Option<JsObject>,  // Vor dem Lauf als globale Variablen definiert
+}
+
+// Verschachtelte Objekte tiefer als das gelten als zyklisch
+const MAX_CONVERSION_DEPTH: usize = 64;
+
+// Ergebnis eines Skriptlaufs. Wird auf dem Worker-Thread erzeugt und erst im
+// Haupt-Thread in JS-Werte umgewandelt.
+pub enum ExecutionOutcome {
+    Completed(Value),
+    ParseFailed(Vec<ParserError>),
+    Failed(ScriptError),
+}
+
+// Parst und führt ein Skript aus; alle Syntaxfehler werden gesammelt gemeldet
+pub fn run_script(interpreter: &mut Interpreter, code: &str) -> ExecutionOutcome {
+    let mut parser = Parser::new(code);
+    let output = parser.parse_with_recovery();
+    if output.has_errors() {
+        return ExecutionOutcome::ParseFailed(output.diagnostics);
+    }
+
+    match interpreter.interpret(output.statements) {
+        Ok(value) => ExecutionOutcome::Completed(value),
+        Err(error) => ExecutionOutcome::Failed(error),
+    }
+}
+
+// Führt ein Skript im libuv-Threadpool aus, damit lange Schleifen den
+// Haupt-Prozess nicht blockieren. `out` läuft weiter über den ThreadsafeFunction-Callback.
+// Der Interpreter ist geteilt, damit Sessions ihn über mehrere Läufe behalten können.
+pub struct ExecuteTask {
+    code: String,
+    interpreter: Arc<Mutex<Interpreter>>,
+    id: Option<String>,
+    limits: ExecutionLimits,
+    globals: Vec<(String, Value)>,
+}
+
+impl Task for ExecuteTask {
+    type Output = ExecutionOutcome;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> NapiResult<Self::Output> {
+        // Ein Interpreter führt immer nur ein Skript gleichzeitig aus
+        let mut interpreter = match self.interpreter.try_lock() {
+            Ok(interpreter) => interpreter,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                return Err(Error::new(Status::GenericFailure, "Interpreter is busy with another script".to_string()));
+            },
+        };
+        interpreter.set_limits(self.limits.clone());
+        for (name, value) in self.globals.drain(..) {
+            interpreter.define_global(name, value);
+        }
+        Ok(run_script(&mut interpreter, &self.code))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> NapiResult<Self::JsValue> {
+        match output {
+            ExecutionOutcome::Completed(value) => value_to_js(&env, &value, 0),
+            ExecutionOutcome::ParseFailed(diagnostics) => {
+                let message = diagnostics.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n");
+                let first = &diagnostics[0];
+                let mut error = create_script_error(&env, "ParseError", first.code(), &message, Some(first.span()))?;
+
+                let mut list = env.create_array_with_length(diagnostics.len())?;
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    let mut entry = env.create_object()?;
+                    entry.set_named_property("code", env.create_string(diagnostic.code())?)?;
+                    entry.set_named_property("message", env.create_string(&diagnostic.to_string())?)?;
+                    set_span(&env, &mut entry, diagnostic.span())?;
+                    list.set_element(i as u32, entry)?;
+                }
+                error.set_named_property("diagnostics", list)?;
+                Err(Error::from(error.into_unknown()))
+            },
+            ExecutionOutcome::Failed(error) => {
+                let message = error.to_string();
+                let mut js_error = create_script_error(&env, "RuntimeError", error.error.code(), &message, error.span)?;
+
+                // Stacktrace des Skripts, sowohl strukturiert als auch als `stack`-Text
+                let entries = error.stack_trace();
+                let mut trace = env.create_array_with_length(entries.len())?;
+                let mut stack = format!("{}: {}", error.error.code(), message);
+                for (i, entry) in entries.iter().enumerate() {
+                    let mut frame = env.create_object()?;
+                    frame.set_named_property("function", env.create_string(&entry.function)?)?;
+                    match entry.span {
+                        Some(span) => {
+                            set_span(&env, &mut frame, span)?;
+                            stack.push_str(&format!("\n    at {} ({})", entry.function, span.start));
+                        },
+                        None => stack.push_str(&format!("\n    at {}", entry.function)),
+                    }
+                    trace.set_element(i as u32, frame)?;
+                }
+                js_error.set_named_property("trace", trace)?;
+                js_error.set_named_property("stack", env.create_string(&stack)?)?;
+
+                // Bei abgebrochenen Skripten auch den erreichten Fortschritt melden
+                if let RuntimeError::Interrupted { steps, elapsed, .. } = error.error {
+                    js_error.set_named_property("steps", env.create_double(steps as f64)?)?;
+                    js_error.set_named_property("elapsedMs", env.create_double(elapsed.as_millis() as f64)?)?;
+                }
+                Err(Error::from(js_error.into_unknown()))
+            },
+        }
+    }
+
+    fn finally(&mut self, _env: Env) -> NapiResult<()> {
+        if let Some(id) = &self.id {
+            RUNNING.lock().unwrap().remove(id);
+        }
+        Ok(())
+    }
+}
+
+// JS-Error mit zusätzlichen Feldern, damit die Oberfläche Art und Position auswerten kann.
+// `kind` unterscheidet Syntax- und Laufzeitfehler, `code` die genaue Fehlerart.
+fn create_script_error(env: &Env, kind: &str, code: &str, message: &str, span: Option<Span>) -> NapiResult<JsObject> {
+    let mut error = env.create_error(Error::from_reason(message))?;
+    error.set_named_property("kind", env.create_string(kind)?)?;
+    error.set_named_property("code", env.create_string(code)?)?;
+    if let Some(span) = span {
+        set_span(env, &mut error, span)?;
+    }
+    Ok(error)
+}
+
+fn set_span(env: &Env, object: &mut JsObject, span: Span) -> NapiResult<()> {
+    object.set_named_property("line", env.create_uint32(span.start.line as u32)?)?;
+    object.set_named_property("column", env.create_uint32(span.start.column as u32)?)?;
+    object.set_named_property("endLine", env.create_uint32(span.end.line as u32)?)?;
+    object.set_named_property("endColumn", env.create_uint32(span.end.column as u32)?)?;
+    Ok(())
+}
+
+// Arrays und Maps werden rekursiv kopiert; Funktionen können den Interpreter nicht verlassen.
+// Skriptwerte können sich selbst enthalten, daher gilt dieselbe Tiefengrenze wie in `value_from_js`.
+pub(crate) fn value_to_js(env: &Env, value: &Value, depth: usize) -> NapiResult<JsUnknown> {
+    if depth > MAX_CONVERSION_DEPTH {
+        return Err(Error::new(
+            Status::GenericFailure,
+            "Script value is nested too deeply or contains a cycle".to_string(),
+        ));
+    }
+
+    Ok(match value {
+        Value::Number(n) => env.create_double(*n)?.into_unknown(),
+        Value::String(s) => env.create_string(s)?.into_unknown(),
+        Value::Boolean(b) => env.get_boolean(*b)?.into_unknown(),
+        Value::Null => env.get_null()?.into_unknown(),
+        Value::Array(elements) => {
+            let elements = elements.lock().unwrap().clone();
+            let mut array = env.create_array_with_length(elements.len())?;
+            for (i, element) in elements.iter().enumerate() {
+                array.set_element(i as u32, value_to_js(env, element, depth + 1)?)?;
+            }
+            array.into_unknown()
+        },
+        Value::Map(entries) => {
+            let entries = entries.lock().unwrap().clone();
+            let mut object = env.create_object()?;
+            for (key, value) in entries.iter() {
+                object.set_named_property(key, value_to_js(env, value, depth + 1)?)?;
+            }
+            object.into_unknown()
+        },
+        Value::NativeFunction(_) | Value::Function(_) | Value::Closure(_) => env.get_null()?.into_unknown(),
+    })
+}
+
+fn globals_from_js(object: JsObject) -> NapiResult<Vec<(String, Value)>> {
+    match value_from_js(object.into_unknown(), 0)? {
+        Value::Map(entries) => Ok(entries.lock().unwrap().clone().into_iter().collect()),
+        _ => Err(Error::new(Status::InvalidArg, "globals must be a plain object".to_string())),
+    }
+}
+
+// Gegenstück zu `value_to_js`. Funktionen und andere JS-Typen ohne Entsprechung werden abgelehnt.
+pub(crate) fn value_from_js(value: JsUnknown, depth: usize) -> NapiResult<Value> {
+    if depth > MAX_CONVERSION_DEPTH {
+        return Err(Error::new(Status::InvalidArg, "Value is nested too deeply or contains a cycle".to_string()));
+    }
+
+    match value.get_type()? {
+        ValueType::Null | ValueType::Undefined => Ok(Value::Null),
+        ValueType::Boolean => Ok(Value::Boolean(JsBoolean::try_from(value)?.get_value()?)),
+        ValueType::Number => Ok(Value::Number(JsNumber::try_from(value)?.get_double()?)),
+        ValueType::String => Ok(Value::String(JsString::try_from(value)?.into_utf8()?.into_owned()?)),
+        ValueType::Object => {
+            let object = JsObject::try_from(value)?;
+            if object.is_array()? {
+                let length = object.get_array_length()?;
+                let mut elements = Vec::with_capacity(length as usize);
+                for i in 0..length {
+                    elements.push(value_from_js(object.get_element::<JsUnknown>(i)?, depth + 1)?);
+                }
+                return Ok(Value::array(elements));
+            }
+
+            let keys = object.get_property_names()?;
+            let mut entries = BTreeMap::new();
+            for i in 0..keys.get_array_length()? {
+                let key = keys.get_element::<JsString>(i)?.into_utf8()?.into_owned()?;
+                let value = object.get_named_property::<JsUnknown>(&key)?;
+                entries.insert(key, value_from_js(value, depth + 1)?);
+            }
+            Ok(Value::map(entries))
+        },
+        other => Err(Error::new(
+            Status::InvalidArg,
+            format!("Cannot pass a JavaScript {} into a script", other),
+        )),
+    }
+}
+
+// Gibt ein Promise zurück, das mit dem letzten Wert des Skripts erfüllt oder mit
+// einem Fehlerobjekt (kind, message, line, column) abgelehnt wird
+#[napi]
+pub fn execute_code(
+    code: String,
+    callback: ThreadsafeFunction<String, ErrorStrategy::Fatal>,
+    options: Option<ExecutionOptions>,
+) -> napi::Result<AsyncTask<ExecuteTask>> {
+    // `napi::Result` ausgeschrieben: Nur daran erkennt das Makro den Fehlerfall und wirft
+    // ihn, bei einem Alias wie `NapiResult` würde der Fehler als Wert zurückgegeben
+    start_execution(code, Arc::new(Mutex::new(create_interpreter(Some(callback)))), options)
+}
+
+// Interpreter, wie ihn die napi-Funktionen verwenden: VM-Backend und Prozess-Builtins
+pub(crate) fn create_interpreter(callback: Option<ThreadsafeFunction<String, ErrorStrategy::Fatal>>) -> Interpreter {
+    let mut interpreter = match callback {
+        Some(callback) => Interpreter::with_callback(callback),
+        None => Interpreter::new(),
+    };
+    interpreter.set_backend(Backend::Bytecode);
+    register_process_builtins(&mut interpreter);
+    interpreter
+}
+
+pub(crate) fn start_execution(
+    code: String,
+    interpreter: Arc<Mutex<Interpreter>>,
+    options: Option<ExecutionOptions>,
+) -> NapiResult<AsyncTask<ExecuteTask>> {
+    Ok(AsyncTask::new(prepare_execution(code, interpreter, options)?))
+}
+
+// Übersetzt die Optionen in Limits und meldet den Lauf unter seiner Kennung an
+pub(crate) fn prepare_execution(
+    code: String,
+    interpreter: Arc<Mutex<Interpreter>>,
+    options: Option<ExecutionOptions>,
+) -> NapiResult<ExecuteTask> {
+    let options = options.unwrap_or_default();
+    let mut limits = ExecutionLimits {
+        max_steps: options.max_steps.map(|steps| steps.max(0) as u64),
+        timeout: options.timeout_ms.map(|ms| Duration::from_millis(ms as u64)),
+        ..Default::default()
+    };
+    if let Some(depth) = options.max_call_depth {
+        limits.max_call_depth = Some(depth as usize);
+    }
+
+    // Umwandeln muss im Haupt-Thread passieren, solange die JS-Werte gültig sind. Erst danach
+    // anmelden, sonst bliebe die Kennung bei ungültigen Globals für immer belegt.
+    let globals = match options.globals {
+        Some(object) => globals_from_js(object)?,
+        None => Vec::new(),
+    };
+
+    if let Some(id) = &options.id {
+        let mut running = RUNNING.lock().unwrap();
+        if running.contains_key(id) {
+            return Err(Error::new(Status::InvalidArg, format!("Execution '{}' is already running", id)));
+        }
+        let token = CancellationToken::new();
+        running.insert(id.clone(), token.clone());
+        limits.cancellation = Some(token);
+    }
+
+    Ok(ExecuteTask { code, interpreter, id: options.id, limits, globals })
+}
+
+// Bricht ein mit `id` gestartetes Skript ab; false, wenn keines mehr läuft
+#[napi]
+pub fn cancel_execution(id: String) -> bool {
+    match RUNNING.lock().unwrap().get(&id) {
+        Some(token) => {
+            token.cancel();
+            true
+        },
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::RuntimeError;
+
+    fn run(code: &str) -> ExecutionOutcome {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_backend(Backend::Bytecode);
+        run_script(&mut interpreter, code)
+    }
+
+    #[test]
+    fn test_completed_with_final_value() {
+        match run("var x = 20; x * 2 + 2;") {
+            ExecutionOutcome::Completed(value) => assert_eq!(value, Value::Number(42.0)),
+            _ => panic!("script should complete"),
+        }
+    }
+
+    #[test]
+    fn test_globals_survive_between_runs() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_backend(Backend::Bytecode);
+        assert!(matches!(run_script(&mut interpreter, "fn double(x) { return x * 2; } var base = 4;"), ExecutionOutcome::Completed(_)));
+        match run_script(&mut interpreter, "double(base) + 1;") {
+            ExecutionOutcome::Completed(value) => assert_eq!(value, Value::Number(9.0)),
+            _ => panic!("second run should see the first run's globals"),
+        }
+    }
+
+    #[test]
+    fn test_parse_failure_collects_diagnostics() {
+        match run("var = 1;\nvar y = ;") {
+            ExecutionOutcome::ParseFailed(diagnostics) => assert_eq!(diagnostics.len(), 2),
+            _ => panic!("script should fail to parse"),
+        }
+    }
+
+    #[test]
+    fn test_runtime_failure_keeps_span() {
+        match run("var x = 1;\nx / 0;") {
+            ExecutionOutcome::Failed(error) => {
+                assert!(matches!(error.error, RuntimeError::DivisionByZero));
+                assert_eq!(error.span.map(|span| span.start.line), Some(2));
+            },
+            _ => panic!("script should fail at runtime"),
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::{HashMap, VecDeque};
+use napi::{Error, Status};
+
+use crate::monitor::{monitor, Snapshot};
+
+// Eine Stunde bei einer Aufnahme pro Sekunde
+pub const DEFAULT_CAPACITY: usize = 3600;
+
+// Ein Messwert; `memory` in Bytes, `cpu_usage` in Prozent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub timestamp: u64,
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+// Ringpuffer: Ist er voll, verdrängt jeder neue Wert den ältesten
+#[derive(Debug, Clone)]
+pub struct Series {
+    points: VecDeque<Point>,
+    capacity: usize,
+}
+
+impl Series {
+    pub fn new(capacity: usize) -> Self {
+        Series {
+            points: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, point: Point) {
+        while self.points.len() >= self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.points.len() > capacity {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn oldest(&self) -> Option<u64> {
+        self.points.front().map(|point| point.timestamp)
+    }
+
+    pub fn newest(&self) -> Option<u64> {
+        self.points.back().map(|point| point.timestamp)
+    }
+
+    // Werte mit `from <= timestamp <= to`; die Zeitstempel sind aufsteigend sortiert
+    pub fn range(&self, from: u64, to: u64) -> impl Iterator<Item = &Point> {
+        let start = self.points.partition_point(|point| point.timestamp < from);
+        self.points.range(start..).take_while(move |point| point.timestamp <= to)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
+impl Aggregate {
+    fn of(values: impl Iterator<Item = f64>) -> Option<Self> {
+        let (mut min, mut max, mut sum, mut count) = (f64::INFINITY, f64::NEG_INFINITY, 0.0, 0usize);
+        for value in values {
+            min = min.min(value);
+            max = max.max(value);
+            sum += value;
+            count += 1;
+        }
+        (count > 0).then(|| Aggregate { min, max, avg: sum / count as f64 })
+    }
+}
+
+// Zusammenfassung aller Werte in `start..end`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub start: u64,
+    pub end: u64,
+    pub samples: usize,
+    pub cpu_usage: Aggregate,
+    pub memory: Aggregate,
+}
+
+// Fasst sortierte Werte in Abschnitte von `bucket_ms` ab `from` zusammen; leere Abschnitte
+// entfallen. Ohne Abschnittslänge wird jeder Wert ein eigener Abschnitt.
+pub fn aggregate<'a>(points: impl Iterator<Item = &'a Point>, from: u64, bucket_ms: Option<u64>) -> Vec<Bucket> {
+    let mut groups: Vec<(u64, u64, Vec<Point>)> = Vec::new();
+    for point in points {
+        let (start, end) = match bucket_ms {
+            Some(bucket_ms) => {
+                let start = from + (point.timestamp.saturating_sub(from) / bucket_ms) * bucket_ms;
+                (start, start + bucket_ms)
+            },
+            None => (point.timestamp, point.timestamp + 1),
+        };
+        match groups.last_mut() {
+            Some((group_start, _, group)) if *group_start == start => group.push(*point),
+            _ => groups.push((start, end, vec![*point])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(start, end, group)| Some(Bucket {
+            start,
+            end,
+            samples: group.len(),
+            cpu_usage: Aggregate::of(group.iter().map(|point| point.cpu_usage as f64))?,
+            memory: Aggregate::of(group.iter().map(|point| point.memory as f64))?,
+        }))
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessSeries {
+    pub name: String,
+    pub series: Series,
+}
+
+// Verlauf je PID und für das Gesamtsystem (CPU gesamt, belegter Speicher)
+pub struct History {
+    capacity: usize,
+    processes: HashMap<u32, ProcessSeries>,
+    system: Series,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity,
+            processes: HashMap::new(),
+            system: Series::new(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.system.set_capacity(capacity);
+        for process in self.processes.values_mut() {
+            process.series.set_capacity(capacity);
+        }
+    }
+
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        let timestamp = snapshot.timestamp;
+        self.system.push(Point {
+            timestamp,
+            cpu_usage: snapshot.system.cpu_usage,
+            memory: snapshot.system.used_memory,
+        });
+
+        for process in &snapshot.system.processes {
+            let capacity = self.capacity;
+            let entry = self.processes.entry(process.pid).or_insert_with(|| ProcessSeries {
+                name: process.name.clone(),
+                series: Series::new(capacity),
+            });
+            // Neu vergebene PID: der alte Verlauf gehört zu einem anderen Prozess
+            if entry.name != process.name {
+                *entry = ProcessSeries { name: process.name.clone(), series: Series::new(capacity) };
+            }
+            entry.series.push(Point { timestamp, cpu_usage: process.cpu_usage, memory: process.memory });
+        }
+
+        // Beendete Prozesse fallen weg, sobald ihr letzter Wert aus dem Systemverlauf verdrängt ist
+        let oldest = self.system.oldest().unwrap_or(timestamp);
+        self.processes.retain(|_, process| process.series.newest().is_some_and(|newest| newest >= oldest));
+    }
+
+    pub fn process(&self, pid: u32) -> Option<&ProcessSeries> {
+        self.processes.get(&pid)
+    }
+
+    pub fn system(&self) -> &Series {
+        &self.system
+    }
+
+    pub fn processes(&self) -> impl Iterator<Item = (u32, &ProcessSeries)> {
+        self.processes.iter().map(|(pid, process)| (*pid, process))
+    }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct HistoryQuery {
+    pub from: Option<f64>,  // Millisekunden seit der Unix-Epoche, ohne Angabe ab dem ältesten Wert
+    pub to: Option<f64>,
+    pub bucket_ms: Option<u32>,  // Ohne Angabe die einzelnen Werte
+}
+
+#[napi(object)]
+pub struct HistoryBucket {
+    pub start: f64,
+    pub end: f64,
+    pub samples: u32,
+    pub cpu_min: f64,
+    pub cpu_max: f64,
+    pub cpu_avg: f64,
+    pub memory_min: f64,  // Bytes
+    pub memory_max: f64,
+    pub memory_avg: f64,
+}
+
+impl From<Bucket> for HistoryBucket {
+    fn from(bucket: Bucket) -> Self {
+        HistoryBucket {
+            start: bucket.start as f64,
+            end: bucket.end as f64,
+            samples: bucket.samples as u32,
+            cpu_min: bucket.cpu_usage.min,
+            cpu_max: bucket.cpu_usage.max,
+            cpu_avg: bucket.cpu_usage.avg,
+            memory_min: bucket.memory.min,
+            memory_max: bucket.memory.max,
+            memory_avg: bucket.memory.avg,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct ProcessHistory {
+    pub pid: u32,
+    pub name: String,
+    pub buckets: Vec<HistoryBucket>,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct HistoryOptions {
+    pub capacity: Option<u32>,  // Werte je Verlauf
+}
+
+pub(crate) struct QueryRange {
+    pub from: Option<u64>,  // Ohne Anfang beginnen die Abschnitte beim ältesten Wert
+    pub to: u64,
+    pub bucket_ms: Option<u64>,
+}
+
+pub(crate) fn query_range(query: Option<HistoryQuery>) -> napi::Result<QueryRange> {
+    let query = query.unwrap_or_default();
+    if query.bucket_ms == Some(0) {
+        return Err(Error::new(Status::InvalidArg, "bucketMs must be greater than 0".to_string()));
+    }
+    Ok(QueryRange {
+        from: query.from.map(|from| from.max(0.0) as u64),
+        to: query.to.map(|to| to.max(0.0) as u64).unwrap_or(u64::MAX),
+        bucket_ms: query.bucket_ms.map(|bucket_ms| bucket_ms as u64),
+    })
+}
+
+pub(crate) fn buckets(series: &Series, range: &QueryRange) -> Vec<HistoryBucket> {
+    // Ein angegebener Anfang gilt auch vor dem ältesten Wert, damit die Abschnitte
+    // verschiedener Prozesse in derselben Abfrage aufeinander ausgerichtet sind
+    let from = range.from.unwrap_or_else(|| series.oldest().unwrap_or(0));
+    aggregate(series.range(from, range.to), from, range.bucket_ms)
+        .into_iter()
+        .map(HistoryBucket::from)
+        .collect()
+}
+
+// Verlauf eines Prozesses aus den Aufnahmen des Samplers; null, wenn keiner vorliegt
+#[napi]
+pub fn get_process_history(pid: u32, query: Option<HistoryQuery>) -> napi::Result<Option<ProcessHistory>> {
+    Ok(process_history(&monitor().history(), pid, &query_range(query)?))
+}
+
+pub(crate) fn process_history(history: &History, pid: u32, range: &QueryRange) -> Option<ProcessHistory> {
+    history.process(pid).map(|process| ProcessHistory {
+        pid,
+        name: process.name.clone(),
+        buckets: buckets(&process.series, range),
+    })
+}
+
+#[napi]
+pub fn get_system_history(query: Option<HistoryQuery>) -> napi::Result<Vec<HistoryBucket>> {
+    let range = query_range(query)?;
+    Ok(buckets(monitor().history().system(), &range))
+}
+
+#[napi]
+pub fn configure_history(options: HistoryOptions) -> napi::Result<()> {
+    if let Some(capacity) = options.capacity {
+        if capacity == 0 {
+            return Err(Error::new(Status::InvalidArg, "capacity must be greater than 0".to_string()));
+        }
+        monitor().history().set_capacity(capacity as usize);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::fixtures::{process, snapshot};
+
+    #[test]
+    fn test_ring_buffer_and_range() {
+        let mut series = Series::new(3);
+        for timestamp in 1..=5 {
+            series.push(Point { timestamp, cpu_usage: 0.0, memory: 0 });
+        }
+        let timestamps: Vec<u64> = series.range(0, u64::MAX).map(|point| point.timestamp).collect();
+        assert_eq!(timestamps, vec![3, 4, 5]);
+        assert_eq!(series.range(4, 4).count(), 1);
+    }
+
+    #[test]
+    fn test_bucket_aggregates() {
+        let points: Vec<Point> = [(0, 10), (400, 30), (1000, 5), (2500, 7)]
+            .iter()
+            .map(|&(timestamp, memory)| Point { timestamp, cpu_usage: memory as f32 / 10.0, memory })
+            .collect();
+
+        let buckets = aggregate(points.iter(), 0, Some(1000));
+        assert_eq!(buckets.len(), 3);
+        assert_eq!((buckets[0].start, buckets[0].end, buckets[0].samples), (0, 1000, 2));
+        assert_eq!(buckets[0].memory, Aggregate { min: 10.0, max: 30.0, avg: 20.0 });
+        assert_eq!((buckets[2].start, buckets[2].memory.avg), (2000, 7.0));
+        assert_eq!(aggregate(points.iter(), 0, None).len(), 4);
+    }
+
+    #[test]
+    fn test_buckets_align_to_requested_start() {
+        let mut series = Series::new(10);
+        for timestamp in [1500, 2600] {
+            series.push(Point { timestamp, cpu_usage: 0.0, memory: 0 });
+        }
+        let starts = |from| -> Vec<f64> {
+            buckets(&series, &QueryRange { from, to: u64::MAX, bucket_ms: Some(1000) })
+                .iter()
+                .map(|bucket| bucket.start)
+                .collect()
+        };
+        assert_eq!(starts(Some(1000)), vec![1000.0, 2000.0]);
+        assert_eq!(starts(None), vec![1500.0, 2500.0]);
+    }
+
+    #[test]
+    fn test_exited_processes_age_out() {
+        let mut history = History::new(2);
+        history.record(&snapshot(1, vec![process(10, "aion.bin", 100, 1.0), process(11, "short", 1, 1.0)]));
+        history.record(&snapshot(2, vec![process(10, "aion.bin", 200, 1.0)]));
+        assert!(history.process(11).is_some());
+
+        history.record(&snapshot(3, vec![process(10, "aion.bin", 300, 1.0), process(11, "other", 1, 1.0)]));
+        assert_eq!(history.process(11).unwrap().series.oldest(), Some(3));
+        assert_eq!(history.process(11).unwrap().name, "other");
+
+        history.record(&snapshot(4, vec![process(10, "aion.bin", 400, 1.0)]));
+        history.record(&snapshot(5, vec![process(10, "aion.bin", 500, 1.0)]));
+        assert!(history.process(11).is_none());
+        let memory: Vec<u64> = history.process(10).unwrap().series.range(0, u64::MAX).map(|point| point.memory).collect();
+        assert_eq!(memory, vec![400, 500]);
+    }
+}
@@ -1,9 +1,19 @@
+use super::types::Span;
+
+// Ausdruck mit seinem Bereich im Quelltext
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
 #[derive(Debug, PartialEq, Clone)]
-pub enum Expression {
+pub enum ExpressionKind {
     // Literale
     Number(f64),
     String(String),
     Boolean(bool),
+    Null,
     Identifier(String),
 
     // Mathematische Operationen
@@ -22,10 +32,25 @@ pub enum Expression {
         callee: Box<Expression>,
         arguments: Vec<Expression>,
     },
+
+    // Arrays und Maps (`p.name` wird als `p["name"]` dargestellt)
+    Array(Vec<Expression>),
+    Map(Vec<(String, Expression)>),
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+    },
+}
+
+// Statement mit seinem Bereich im Quelltext
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Statement {
+pub enum StatementKind {
     // Variablendeklaration
     VarDeclaration {
         name: String,
@@ -46,10 +71,21 @@ pub enum Statement {
         else_branch: Option<Box<Statement>>,
     },
     While {
+        label: Option<String>,
         condition: Expression,
         body: Box<Statement>,
     },
+    DoWhile {
+        label: Option<String>,
+        body: Box<Statement>,
+        condition: Expression,
+    },
+    Loop {
+        label: Option<String>,
+        body: Box<Statement>,
+    },
     For {
+        label: Option<String>,
         initializer: Option<Box<Statement>>,
         condition: Option<Expression>,
         increment: Option<Expression>,
@@ -71,9 +107,13 @@ pub enum Statement {
 
     // Return-Statement
     Return(Option<Expression>),
+
+    // Schleifensprünge, optional mit Label der Zielschleife
+    Break(Option<String>),
+    Continue(Option<String>),
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum BinaryOperator {
     // Arithmetische Operatoren
     Add,
@@ -97,7 +137,7 @@ pub enum BinaryOperator {
     Assign,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum UnaryOperator {
     Minus,
     Not,
@@ -119,43 +159,93 @@ impl BinaryOperator {
     }
 }
 
-// Hilfsfunktionen für AST-Erstellung
+// Gleichheit vergleicht nur die Struktur, nicht die Position im Quelltext
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl From<ExpressionKind> for Expression {
+    fn from(kind: ExpressionKind) -> Self {
+        Expression { kind, span: Span::default() }
+    }
+}
+
+impl From<StatementKind> for Statement {
+    fn from(kind: StatementKind) -> Self {
+        Statement { kind, span: Span::default() }
+    }
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Statement { kind, span }
+    }
+}
+
+// Hilfsfunktionen für AST-Erstellung (ohne Position, z.B. für Tests)
 impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Expression { kind, span }
+    }
+
     pub fn number(value: f64) -> Self {
-        Expression::Number(value)
+        ExpressionKind::Number(value).into()
     }
 
     pub fn string(value: String) -> Self {
-        Expression::String(value)
+        ExpressionKind::String(value).into()
     }
 
     pub fn boolean(value: bool) -> Self {
-        Expression::Boolean(value)
+        ExpressionKind::Boolean(value).into()
     }
 
     pub fn identifier(name: String) -> Self {
-        Expression::Identifier(name)
+        ExpressionKind::Identifier(name).into()
     }
 
     pub fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Self {
-        Expression::Binary {
+        ExpressionKind::Binary {
             left: Box::new(left),
             operator,
             right: Box::new(right),
-        }
+        }.into()
     }
 
     pub fn unary(operator: UnaryOperator, operand: Expression) -> Self {
-        Expression::Unary {
+        ExpressionKind::Unary {
             operator,
             operand: Box::new(operand),
-        }
+        }.into()
     }
 
     pub fn call(callee: Expression, arguments: Vec<Expression>) -> Self {
-        Expression::Call {
+        ExpressionKind::Call {
             callee: Box::new(callee),
             arguments,
-        }
+        }.into()
+    }
+
+    pub fn array(elements: Vec<Expression>) -> Self {
+        ExpressionKind::Array(elements).into()
+    }
+
+    pub fn map(entries: Vec<(String, Expression)>) -> Self {
+        ExpressionKind::Map(entries).into()
+    }
+
+    pub fn index(object: Expression, index: Expression) -> Self {
+        ExpressionKind::Index {
+            object: Box::new(object),
+            index: Box::new(index),
+        }.into()
     }
 } 
\ No newline at end of file
@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+use super::ast::{BinaryOperator, UnaryOperator};
+use super::interpreter::Value;
+use super::collector;
+use super::types::Span;
+
+// Instruktionen der Stack-VM. Sprungziele sind absolute Positionen im Chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpCode {
+    // Konstanten und Literale
+    Constant(usize),
+    Null,
+    True,
+    False,
+
+    // Stack-Verwaltung
+    Pop,
+    Dup,
+    SetResult,  // Ergebniswert des Skripts setzen (nur auf Skriptebene)
+
+    // Variablen (Globale über den Namensindex, Lokale über den Slot)
+    DefineGlobal(usize),
+    GetGlobal(usize),
+    SetGlobal(usize),
+    DefineLocal(usize),
+    GetLocal(usize),
+    SetLocal(usize),
+    GetUpvalue(usize),
+    SetUpvalue(usize),
+
+    // Operatoren
+    Binary(BinaryOperator),
+    Unary(UnaryOperator),
+
+    // Sprünge
+    Jump(usize),
+    PopJumpIfFalse(usize),
+    JumpIfFalseOrPop(usize),  // für &&: linken Operanden behalten, wenn er entscheidet
+    JumpIfTrueOrPop(usize),   // für ||
+
+    // Funktionen
+    Call(usize),
+    Closure(usize),
+    Return,
+    Halt,
+
+    // Arrays und Maps
+    BuildArray(usize),
+    BuildMap(usize),
+    GetIndex,
+    SetIndex,
+}
+
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub spans: Vec<Span>,  // Quelltextbereich je Instruktion, für Fehlermeldungen
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    pub functions: Vec<Arc<FunctionProto>>,
+}
+
+// Herkunft einer eingefangenen Variable: Slot der direkt umschließenden Funktion
+// oder deren eigener Upvalue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpvalueRef {
+    pub is_local: bool,
+    pub index: usize,
+}
+
+// Kompilierte Funktion, aus der zur Laufzeit Closures erzeugt werden
+#[derive(Debug)]
+pub struct FunctionProto {
+    pub name: String,
+    pub arity: usize,
+    pub local_count: usize,
+    pub upvalues: Vec<UpvalueRef>,
+    pub chunk: Chunk,
+}
+
+// Laufzeitwert einer kompilierten Funktion samt eingefangener Variablen
+pub struct Closure {
+    pub function: Arc<FunctionProto>,
+    pub upvalues: Vec<Arc<Mutex<Value>>>,
+}
+
+// Eine Zelle kann über diese Closure auf sich selbst verweisen (z.B. bei Rekursion)
+impl Drop for Closure {
+    fn drop(&mut self) {
+        collector::release_cells(self.upvalues.iter());
+    }
+}
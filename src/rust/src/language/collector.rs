@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use super::bytecode::Closure;
+use super::interpreter::{ScriptFunction, SharedEnvironment, Value};
+
+// Räumt Referenzzyklen zwischen Umgebungen, Funktionen und Werten ab. Funktionen halten die
+// Umgebung bzw. die Zellen, die sie einfangen, per Arc; liegen sie selbst darin (lokale
+// Funktionen, Rekursion über den eigenen Namen, Funktionen in Arrays), gibt Arc sie nie frei.
+//
+// Wenn eine Referenz auf eine Umgebung oder Zelle wegfällt, wird der davon erreichbare Teilgraph
+// untersucht: Zählt ein Knoten mehr starke Referenzen, als aus dem Teilgraph selbst kommen, hält
+// ihn etwas von außen; er und alles, was er erreicht, bleibt. Der Rest ist nur noch über Zyklen
+// erreichbar und wird geleert.
+//
+// Im Zweifel bleibt ein Knoten bestehen: Globals (Umgebungen ohne umschließende) werden nicht
+// untersucht, gesperrte Knoten ebenso wenig, und zu große Teilgraphen brechen die Suche ab.
+
+// Ab so vielen Knoten wird die Suche abgebrochen
+const MAX_NODES: usize = 10_000;
+
+// Knoten im Referenzgraph; jeder Eintrag hält genau einen eigenen Arc-Klon
+enum Node {
+    Environment(SharedEnvironment),
+    Function(Arc<ScriptFunction>),
+    Closure(Arc<Closure>),
+    Cell(Arc<Mutex<Value>>),
+    Array(Arc<Mutex<Vec<Value>>>),
+    Map(Arc<Mutex<BTreeMap<String, Value>>>),
+}
+
+impl Node {
+    fn of(value: &Value) -> Option<Node> {
+        match value {
+            Value::Function(function) => Some(Node::Function(function.clone())),
+            Value::Closure(closure) => Some(Node::Closure(closure.clone())),
+            Value::Array(elements) => Some(Node::Array(elements.clone())),
+            Value::Map(entries) => Some(Node::Map(entries.clone())),
+            _ => None,
+        }
+    }
+
+    fn key(&self) -> *const () {
+        match self {
+            Node::Environment(environment) => Arc::as_ptr(environment) as *const (),
+            Node::Function(function) => Arc::as_ptr(function) as *const (),
+            Node::Closure(closure) => Arc::as_ptr(closure) as *const (),
+            Node::Cell(cell) => Arc::as_ptr(cell) as *const (),
+            Node::Array(elements) => Arc::as_ptr(elements) as *const (),
+            Node::Map(entries) => Arc::as_ptr(entries) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Environment(environment) => Arc::strong_count(environment),
+            Node::Function(function) => Arc::strong_count(function),
+            Node::Closure(closure) => Arc::strong_count(closure),
+            Node::Cell(cell) => Arc::strong_count(cell),
+            Node::Array(elements) => Arc::strong_count(elements),
+            Node::Map(entries) => Arc::strong_count(entries),
+        }
+    }
+
+    // Je gehaltener starker Referenz ein Eintrag. `None` für Knoten, die nicht untersucht
+    // werden können (gesperrt oder Globals); sie gelten als von außen gehalten.
+    fn children(&self) -> Option<Vec<Node>> {
+        match self {
+            Node::Environment(environment) => {
+                let environment = environment.try_lock().ok()?;
+                let enclosing = environment.enclosing()?.clone();
+                let mut children: Vec<Node> = environment.values().filter_map(|(_, value)| Node::of(value)).collect();
+                children.push(Node::Environment(enclosing));
+                Some(children)
+            },
+            Node::Function(function) => Some(vec![Node::Environment(function.closure.clone())]),
+            Node::Closure(closure) => Some(closure.upvalues.iter().cloned().map(Node::Cell).collect()),
+            Node::Cell(cell) => Some(Node::of(&*cell.try_lock().ok()?).into_iter().collect()),
+            Node::Array(elements) => Some(elements.try_lock().ok()?.iter().filter_map(Node::of).collect()),
+            Node::Map(entries) => Some(entries.try_lock().ok()?.values().filter_map(Node::of).collect()),
+        }
+    }
+
+    // Leert den Knoten; die Inhalte landen in `garbage` und werden erst ohne Sperre freigegeben
+    fn clear(&self, garbage: &mut Vec<Value>) {
+        match self {
+            Node::Environment(environment) => {
+                if let Ok(mut environment) = environment.try_lock() {
+                    garbage.extend(environment.take_values().into_values());
+                }
+            },
+            // Funktionen und Closures werden über ihre Umgebung bzw. Zellen frei
+            Node::Function(_) | Node::Closure(_) => {},
+            Node::Cell(cell) => {
+                if let Ok(mut value) = cell.try_lock() {
+                    garbage.push(std::mem::replace(&mut *value, Value::Null));
+                }
+            },
+            Node::Array(elements) => {
+                if let Ok(mut elements) = elements.try_lock() {
+                    garbage.append(&mut elements);
+                }
+            },
+            Node::Map(entries) => {
+                if let Ok(mut entries) = entries.try_lock() {
+                    garbage.extend(std::mem::take(&mut *entries).into_values());
+                }
+            },
+        }
+    }
+}
+
+// Die Referenz des Aufrufers auf `environment` fällt gleich weg
+pub(super) fn release_environment(environment: &SharedEnvironment) {
+    if Arc::strong_count(environment) > 1 {
+        collect(vec![Node::Environment(environment.clone())]);
+    }
+}
+
+// Die Referenzen des Aufrufers auf `cells` fallen gleich weg
+pub(super) fn release_cells<'a>(cells: impl Iterator<Item = &'a Arc<Mutex<Value>>>) {
+    let roots: Vec<Node> = cells
+        .filter(|cell| Arc::strong_count(cell) > 1)
+        .map(|cell| Node::Cell(cell.clone()))
+        .collect();
+    if !roots.is_empty() {
+        collect(roots);
+    }
+}
+
+// `roots` sind Knoten, von denen der Aufrufer je eine Referenz aufgibt
+fn collect(roots: Vec<Node>) {
+    let root_count = roots.len();
+    let mut nodes = roots;
+    let mut index: HashMap<*const (), usize> = nodes.iter().enumerate().map(|(i, node)| (node.key(), i)).collect();
+    let mut edges: Vec<Option<Vec<usize>>> = Vec::new();
+
+    while edges.len() < nodes.len() {
+        if nodes.len() > MAX_NODES {
+            return;
+        }
+        let children = nodes[edges.len()].children().map(|children| {
+            children
+                .into_iter()
+                .map(|child| match index.get(&child.key()) {
+                    Some(&i) => i,
+                    None => {
+                        index.insert(child.key(), nodes.len());
+                        nodes.push(child);
+                        nodes.len() - 1
+                    },
+                })
+                .collect()
+        });
+        edges.push(children);
+    }
+
+    // Jetzt hält der Graph genau einen Klon je Knoten, die Wurzeln zusätzlich die des Aufrufers
+    let mut internal = vec![0; nodes.len()];
+    for &child in edges.iter().flatten().flatten() {
+        internal[child] += 1;
+    }
+
+    let mut live = vec![false; nodes.len()];
+    let mut pending: Vec<usize> = (0..nodes.len())
+        .filter(|&i| {
+            let held = 1 + usize::from(i < root_count) + internal[i];
+            edges[i].is_none() || nodes[i].strong_count() > held
+        })
+        .collect();
+    while let Some(i) = pending.pop() {
+        if live[i] {
+            continue;
+        }
+        live[i] = true;
+        pending.extend(edges[i].iter().flatten().copied());
+    }
+
+    let mut garbage = Vec::new();
+    for (node, _) in nodes.iter().zip(&live).filter(|(_, live)| !**live) {
+        node.clear(&mut garbage);
+    }
+    drop(nodes);
+    drop(garbage);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::interpreter::Environment;
+    use super::super::bytecode::{Chunk, FunctionProto, UpvalueRef};
+
+    // Wie eine rekursive lokale Funktion der VM: Die Zelle enthält die Closure, die sie einfängt
+    fn self_capturing_closure() -> (Arc<Mutex<Value>>, Arc<Closure>) {
+        let cell = Arc::new(Mutex::new(Value::Null));
+        let function = Arc::new(FunctionProto {
+            name: "step".to_string(),
+            arity: 0,
+            local_count: 0,
+            upvalues: vec![UpvalueRef { is_local: true, index: 0 }],
+            chunk: Chunk::default(),
+        });
+        let closure = Arc::new(Closure { function, upvalues: vec![cell.clone()] });
+        *cell.lock().unwrap() = Value::Closure(closure.clone());
+        (cell, closure)
+    }
+
+    #[test]
+    fn test_keeps_externally_held_environment() {
+        let globals = Environment::new().into_shared();
+        let scope = Environment::with_enclosing(globals).into_shared();
+        let function = Arc::new(ScriptFunction {
+            name: "f".to_string(),
+            params: Vec::new(),
+            body: Vec::new(),
+            closure: scope.clone(),
+        });
+        scope.lock().unwrap().define("f".to_string(), Value::Function(function.clone()));
+
+        // `function` hält den Scope noch von außen
+        release_environment(&scope);
+        assert_eq!(scope.lock().unwrap().values().count(), 1);
+
+        drop(function);
+        let weak = Arc::downgrade(&scope);
+        release_environment(&scope);
+        drop(scope);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_collects_self_capturing_cell() {
+        let (cell, closure) = self_capturing_closure();
+        let weak = Arc::downgrade(&closure);
+        drop(closure);
+
+        release_cells(std::iter::once(&cell));
+        drop(cell);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_keeps_cell_of_running_closure() {
+        let (cell, closure) = self_capturing_closure();
+
+        // Die Closure wird noch von außen gehalten, ihre Zelle darf nicht geleert werden
+        release_cells(std::iter::once(&cell));
+        drop(cell);
+        assert!(matches!(&*closure.upvalues[0].lock().unwrap(), Value::Closure(_)));
+    }
+}
@@ -0,0 +1,647 @@
+use std::sync::Arc;
+use super::ast::{Expression, ExpressionKind, Statement, StatementKind, BinaryOperator};
+use super::bytecode::{Chunk, FunctionProto, OpCode, UpvalueRef};
+use super::error::{RuntimeError, ScriptError};
+use super::interpreter::Value;
+use super::types::Span;
+
+struct LocalVar {
+    name: String,
+    depth: usize,
+}
+
+// Ziel für break/continue: eine Schleife oder (nur für unbenanntes break) ein Switch
+struct JumpScope {
+    label: Option<String>,
+    is_loop: bool,
+    break_jumps: Vec<usize>,
+    continue_jumps: Vec<usize>,
+}
+
+// Zustand einer Funktion, die gerade kompiliert wird
+struct FunctionState {
+    name: String,
+    arity: usize,
+    chunk: Chunk,
+    locals: Vec<LocalVar>,
+    local_count: usize,
+    scope_depth: usize,
+    upvalues: Vec<UpvalueRef>,
+    jump_scopes: Vec<JumpScope>,
+    is_script: bool,
+}
+
+impl FunctionState {
+    fn new(name: String, arity: usize, is_script: bool) -> Self {
+        FunctionState {
+            name,
+            arity,
+            chunk: Chunk::default(),
+            locals: Vec::new(),
+            local_count: 0,
+            // Auf Skriptebene sind Variablen global, in Funktionen beginnt der Körper bei Tiefe 1
+            scope_depth: if is_script { 0 } else { 1 },
+            upvalues: Vec::new(),
+            jump_scopes: Vec::new(),
+            is_script,
+        }
+    }
+
+    fn resolve_local(&self, name: &str) -> Option<usize> {
+        self.locals.iter().rposition(|local| local.name == name)
+    }
+
+    fn add_upvalue(&mut self, is_local: bool, index: usize) -> usize {
+        let upvalue = UpvalueRef { is_local, index };
+        if let Some(existing) = self.upvalues.iter().position(|u| *u == upvalue) {
+            return existing;
+        }
+        self.upvalues.push(upvalue);
+        self.upvalues.len() - 1
+    }
+
+    fn into_proto(self) -> FunctionProto {
+        FunctionProto {
+            name: self.name,
+            arity: self.arity,
+            local_count: self.local_count,
+            upvalues: self.upvalues,
+            chunk: self.chunk,
+        }
+    }
+}
+
+// Übersetzt den AST aus `Parser::parse_program` in Bytecode für die `Vm`.
+// Lokale Variablen werden zur Übersetzungszeit auf Slots aufgelöst, alles
+// auf Skriptebene bleibt wie im Tree-Walker global.
+pub struct Compiler {
+    states: Vec<FunctionState>,
+    span: Span,  // Bereich des Knotens, der gerade übersetzt wird
+}
+
+enum VariableRef {
+    Local(usize),
+    Upvalue(usize),
+    Global(usize),
+}
+
+impl Compiler {
+    pub fn compile(statements: &[Statement]) -> Result<Arc<FunctionProto>, ScriptError> {
+        let mut compiler = Compiler {
+            states: vec![FunctionState::new("<script>".to_string(), 0, true)],
+            span: Span::default(),
+        };
+
+        for statement in statements {
+            compiler.compile_statement(statement)?;
+        }
+        compiler.emit(OpCode::Halt);
+
+        let script = compiler.states.pop().expect("script state");
+        Ok(Arc::new(script.into_proto()))
+    }
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.states.last_mut().expect("function state")
+    }
+
+    fn emit(&mut self, op: OpCode) -> usize {
+        let span = self.span;
+        let chunk = &mut self.state().chunk;
+        chunk.code.push(op);
+        chunk.spans.push(span);
+        chunk.code.len() - 1
+    }
+
+    fn error(&self, error: RuntimeError) -> ScriptError {
+        ScriptError::from(error).or_span(self.span)
+    }
+
+    fn current_offset(&mut self) -> usize {
+        self.state().chunk.code.len()
+    }
+
+    fn patch_jump(&mut self, at: usize, target: usize) {
+        let op = match self.state().chunk.code[at] {
+            OpCode::Jump(_) => OpCode::Jump(target),
+            OpCode::PopJumpIfFalse(_) => OpCode::PopJumpIfFalse(target),
+            OpCode::JumpIfFalseOrPop(_) => OpCode::JumpIfFalseOrPop(target),
+            OpCode::JumpIfTrueOrPop(_) => OpCode::JumpIfTrueOrPop(target),
+            other => unreachable!("cannot patch {:?}", other),
+        };
+        self.state().chunk.code[at] = op;
+    }
+
+    fn add_constant(&mut self, value: Value) -> usize {
+        let constants = &mut self.state().chunk.constants;
+        constants.push(value);
+        constants.len() - 1
+    }
+
+    fn add_name(&mut self, name: &str) -> usize {
+        let names = &mut self.state().chunk.names;
+        if let Some(index) = names.iter().position(|n| n == name) {
+            return index;
+        }
+        names.push(name.to_string());
+        names.len() - 1
+    }
+
+    // Ergebniswert wie beim Tree-Walker: auf Skriptebene merkt sich die VM den letzten Wert
+    fn emit_statement_result(&mut self) {
+        if self.state().is_script {
+            self.emit(OpCode::SetResult);
+        } else {
+            self.emit(OpCode::Pop);
+        }
+    }
+
+    fn reset_result(&mut self) {
+        if self.state().is_script {
+            self.emit(OpCode::Null);
+            self.emit(OpCode::SetResult);
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.state().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        let state = self.state();
+        state.scope_depth -= 1;
+        let depth = state.scope_depth;
+        while state.locals.last().is_some_and(|local| local.depth > depth) {
+            state.locals.pop();
+        }
+    }
+
+    fn declare_local(&mut self, name: &str) -> usize {
+        let state = self.state();
+        let depth = state.scope_depth;
+        // Erneute Deklaration im selben Scope überschreibt die Variable
+        if let Some(slot) = state.locals.iter().rposition(|local| local.name == name && local.depth == depth) {
+            return slot;
+        }
+        state.locals.push(LocalVar { name: name.to_string(), depth });
+        state.local_count = state.local_count.max(state.locals.len());
+        state.locals.len() - 1
+    }
+
+    fn define_variable(&mut self, name: &str) {
+        if self.state().scope_depth == 0 {
+            let index = self.add_name(name);
+            self.emit(OpCode::DefineGlobal(index));
+        } else {
+            let slot = self.declare_local(name);
+            self.emit(OpCode::DefineLocal(slot));
+        }
+    }
+
+    fn resolve_variable(&mut self, name: &str) -> VariableRef {
+        let current = self.states.len() - 1;
+        if let Some(slot) = self.states[current].resolve_local(name) {
+            return VariableRef::Local(slot);
+        }
+        if let Some(index) = self.resolve_upvalue(current, name) {
+            return VariableRef::Upvalue(index);
+        }
+        VariableRef::Global(self.add_name(name))
+    }
+
+    fn resolve_upvalue(&mut self, state_index: usize, name: &str) -> Option<usize> {
+        if state_index == 0 {
+            return None;
+        }
+        let enclosing = state_index - 1;
+        if let Some(slot) = self.states[enclosing].resolve_local(name) {
+            return Some(self.states[state_index].add_upvalue(true, slot));
+        }
+        let index = self.resolve_upvalue(enclosing, name)?;
+        Some(self.states[state_index].add_upvalue(false, index))
+    }
+
+    // Instruktionen bekommen den Bereich des innersten Knotens, aus dem sie stammen
+    fn compile_statement(&mut self, statement: &Statement) -> Result<(), ScriptError> {
+        let outer_span = std::mem::replace(&mut self.span, statement.span);
+        let result = self.compile_statement_kind(&statement.kind);
+        self.span = outer_span;
+        result
+    }
+
+    fn compile_statement_kind(&mut self, statement: &StatementKind) -> Result<(), ScriptError> {
+        match statement {
+            StatementKind::Expression(expr) => {
+                self.compile_expression(expr)?;
+                self.emit_statement_result();
+            },
+            StatementKind::VarDeclaration { name, initializer } => {
+                match initializer {
+                    Some(expr) => self.compile_expression(expr)?,
+                    None => {
+                        self.emit(OpCode::Null);
+                    },
+                }
+                if self.state().is_script {
+                    self.emit(OpCode::Dup);
+                    self.emit(OpCode::SetResult);
+                }
+                self.define_variable(name);
+            },
+            StatementKind::FunctionDeclaration { name, params, body } => {
+                self.reset_result();
+                if self.state().scope_depth == 0 {
+                    self.compile_function(name, params, body)?;
+                    let index = self.add_name(name);
+                    self.emit(OpCode::DefineGlobal(index));
+                } else {
+                    // Erst den Slot anlegen, damit sich die Funktion selbst einfangen kann
+                    let slot = self.declare_local(name);
+                    self.emit(OpCode::Null);
+                    self.emit(OpCode::DefineLocal(slot));
+                    self.compile_function(name, params, body)?;
+                    self.emit(OpCode::SetLocal(slot));
+                    self.emit(OpCode::Pop);
+                }
+            },
+            StatementKind::Block(statements) => {
+                self.reset_result();
+                self.begin_scope();
+                for stmt in statements {
+                    self.compile_statement(stmt)?;
+                }
+                self.end_scope();
+            },
+            StatementKind::If { condition, then_branch, else_branch } => {
+                self.reset_result();
+                self.compile_expression(condition)?;
+                let else_jump = self.emit(OpCode::PopJumpIfFalse(0));
+                self.compile_statement(then_branch)?;
+                let end_jump = self.emit(OpCode::Jump(0));
+                let else_start = self.current_offset();
+                self.patch_jump(else_jump, else_start);
+                if let Some(else_branch) = else_branch {
+                    self.compile_statement(else_branch)?;
+                }
+                let end = self.current_offset();
+                self.patch_jump(end_jump, end);
+            },
+            StatementKind::While { label, condition, body } => {
+                self.reset_result();
+                let loop_start = self.current_offset();
+                self.compile_expression(condition)?;
+                let exit_jump = self.emit(OpCode::PopJumpIfFalse(0));
+                self.push_jump_scope(label, true);
+                self.compile_nested(body)?;
+                self.emit(OpCode::Jump(loop_start));
+                let exit = self.current_offset();
+                self.patch_jump(exit_jump, exit);
+                self.pop_jump_scope(loop_start, exit);
+            },
+            StatementKind::DoWhile { label, body, condition } => {
+                self.reset_result();
+                let loop_start = self.current_offset();
+                self.push_jump_scope(label, true);
+                self.compile_nested(body)?;
+                let continue_target = self.current_offset();
+                self.compile_expression(condition)?;
+                let exit_jump = self.emit(OpCode::PopJumpIfFalse(0));
+                self.emit(OpCode::Jump(loop_start));
+                let exit = self.current_offset();
+                self.patch_jump(exit_jump, exit);
+                self.pop_jump_scope(continue_target, exit);
+            },
+            StatementKind::Loop { label, body } => {
+                self.reset_result();
+                let loop_start = self.current_offset();
+                self.push_jump_scope(label, true);
+                self.compile_nested(body)?;
+                self.emit(OpCode::Jump(loop_start));
+                let exit = self.current_offset();
+                self.pop_jump_scope(loop_start, exit);
+            },
+            StatementKind::For { label, initializer, condition, increment, body } => {
+                // Der Initializer läuft wie im Tree-Walker im aktuellen Scope
+                if let Some(init) = initializer {
+                    self.compile_statement(init)?;
+                }
+                self.reset_result();
+                let loop_start = self.current_offset();
+                let exit_jump = match condition {
+                    Some(condition) => {
+                        self.compile_expression(condition)?;
+                        Some(self.emit(OpCode::PopJumpIfFalse(0)))
+                    },
+                    None => None,
+                };
+                self.push_jump_scope(label, true);
+                self.compile_nested(body)?;
+                let continue_target = self.current_offset();
+                if let Some(increment) = increment {
+                    self.compile_expression(increment)?;
+                    self.emit(OpCode::Pop);
+                }
+                self.emit(OpCode::Jump(loop_start));
+                let exit = self.current_offset();
+                if let Some(exit_jump) = exit_jump {
+                    self.patch_jump(exit_jump, exit);
+                }
+                self.pop_jump_scope(continue_target, exit);
+            },
+            StatementKind::Switch { condition, cases, default } => {
+                self.reset_result();
+                self.compile_expression(condition)?;
+                self.push_jump_scope(&None, false);
+
+                let mut end_jumps = Vec::new();
+                for (case_value, case_body) in cases {
+                    self.emit(OpCode::Dup);
+                    self.compile_expression(case_value)?;
+                    self.emit(OpCode::Binary(BinaryOperator::Equal));
+                    let next_case = self.emit(OpCode::PopJumpIfFalse(0));
+                    self.emit(OpCode::Pop);
+                    self.compile_case_body(case_body)?;
+                    end_jumps.push(self.emit(OpCode::Jump(0)));
+                    let next = self.current_offset();
+                    self.patch_jump(next_case, next);
+                }
+
+                self.emit(OpCode::Pop);
+                if let Some(default_body) = default {
+                    self.compile_case_body(default_body)?;
+                }
+
+                let end = self.current_offset();
+                for jump in end_jumps {
+                    self.patch_jump(jump, end);
+                }
+                self.pop_jump_scope(end, end);
+            },
+            StatementKind::Return(expr) => {
+                match expr {
+                    Some(expr) => self.compile_expression(expr)?,
+                    None => {
+                        self.emit(OpCode::Null);
+                    },
+                }
+                self.emit(OpCode::Return);
+            },
+            StatementKind::Break(label) => {
+                let target = self.find_jump_scope(label, true)?;
+                let jump = self.emit(OpCode::Jump(0));
+                self.state().jump_scopes[target].break_jumps.push(jump);
+            },
+            StatementKind::Continue(label) => {
+                let target = self.find_jump_scope(label, false)?;
+                let jump = self.emit(OpCode::Jump(0));
+                self.state().jump_scopes[target].continue_jumps.push(jump);
+            },
+        }
+        Ok(())
+    }
+
+    // Schleifenkörper ohne eigenen Block bekommen in Funktionen trotzdem einen Scope
+    fn compile_nested(&mut self, body: &Statement) -> Result<(), ScriptError> {
+        if matches!(body.kind, StatementKind::Block(_)) || self.state().scope_depth == 0 {
+            return self.compile_statement(body);
+        }
+        self.begin_scope();
+        self.compile_statement(body)?;
+        self.end_scope();
+        Ok(())
+    }
+
+    fn compile_case_body(&mut self, statements: &[Statement]) -> Result<(), ScriptError> {
+        let scoped = self.state().scope_depth > 0;
+        if scoped {
+            self.begin_scope();
+        }
+        for stmt in statements {
+            self.compile_statement(stmt)?;
+        }
+        if scoped {
+            self.end_scope();
+        }
+        Ok(())
+    }
+
+    fn push_jump_scope(&mut self, label: &Option<String>, is_loop: bool) {
+        self.state().jump_scopes.push(JumpScope {
+            label: label.clone(),
+            is_loop,
+            break_jumps: Vec::new(),
+            continue_jumps: Vec::new(),
+        });
+    }
+
+    fn pop_jump_scope(&mut self, continue_target: usize, break_target: usize) {
+        let scope = self.state().jump_scopes.pop().expect("jump scope");
+        for jump in scope.continue_jumps {
+            self.patch_jump(jump, continue_target);
+        }
+        for jump in scope.break_jumps {
+            self.patch_jump(jump, break_target);
+        }
+    }
+
+    fn find_jump_scope(&mut self, label: &Option<String>, is_break: bool) -> Result<usize, ScriptError> {
+        let scopes = &self.state().jump_scopes;
+        let target = scopes.iter().rposition(|scope| match label {
+            Some(label) => scope.is_loop && scope.label.as_ref() == Some(label),
+            None => scope.is_loop || is_break,
+        });
+        target.ok_or_else(|| {
+            let keyword = if is_break { "break" } else { "continue" };
+            self.error(RuntimeError::Custom(format!("'{}' outside of loop", keyword)))
+        })
+    }
+
+    fn compile_function(&mut self, name: &str, params: &[String], body: &[Statement]) -> Result<(), ScriptError> {
+        self.states.push(FunctionState::new(name.to_string(), params.len(), false));
+        for param in params {
+            self.declare_local(param);
+        }
+        for stmt in body {
+            self.compile_statement(stmt)?;
+        }
+        // Ohne `return` liefert eine Funktion null
+        self.emit(OpCode::Null);
+        self.emit(OpCode::Return);
+
+        let function = self.states.pop().expect("function state").into_proto();
+        let functions = &mut self.state().chunk.functions;
+        functions.push(Arc::new(function));
+        let index = functions.len() - 1;
+        self.emit(OpCode::Closure(index));
+        Ok(())
+    }
+
+    fn compile_expression(&mut self, expression: &Expression) -> Result<(), ScriptError> {
+        let outer_span = std::mem::replace(&mut self.span, expression.span);
+        let result = self.compile_expression_kind(&expression.kind);
+        self.span = outer_span;
+        result
+    }
+
+    fn compile_expression_kind(&mut self, expression: &ExpressionKind) -> Result<(), ScriptError> {
+        match expression {
+            ExpressionKind::Number(n) => {
+                let index = self.add_constant(Value::Number(*n));
+                self.emit(OpCode::Constant(index));
+            },
+            ExpressionKind::String(s) => {
+                let index = self.add_constant(Value::String(s.clone()));
+                self.emit(OpCode::Constant(index));
+            },
+            ExpressionKind::Boolean(true) => {
+                self.emit(OpCode::True);
+            },
+            ExpressionKind::Boolean(false) => {
+                self.emit(OpCode::False);
+            },
+            ExpressionKind::Null => {
+                self.emit(OpCode::Null);
+            },
+            ExpressionKind::Identifier(name) => {
+                let op = match self.resolve_variable(name) {
+                    VariableRef::Local(slot) => OpCode::GetLocal(slot),
+                    VariableRef::Upvalue(index) => OpCode::GetUpvalue(index),
+                    VariableRef::Global(index) => OpCode::GetGlobal(index),
+                };
+                self.emit(op);
+            },
+            ExpressionKind::Binary { left, operator: BinaryOperator::Assign, right } => {
+                match &left.kind {
+                    ExpressionKind::Identifier(name) => {
+                        self.compile_expression(right)?;
+                        let op = match self.resolve_variable(name) {
+                            VariableRef::Local(slot) => OpCode::SetLocal(slot),
+                            VariableRef::Upvalue(index) => OpCode::SetUpvalue(index),
+                            VariableRef::Global(index) => OpCode::SetGlobal(index),
+                        };
+                        self.emit(op);
+                    },
+                    ExpressionKind::Index { object, index } => {
+                        self.compile_expression(object)?;
+                        self.compile_expression(index)?;
+                        self.compile_expression(right)?;
+                        self.emit(OpCode::SetIndex);
+                    },
+                    _ => return Err(self.error(RuntimeError::TypeError(
+                        "Left side of assignment must be a variable or index".to_string()
+                    ))),
+                }
+            },
+            ExpressionKind::Binary { left, operator: BinaryOperator::And, right } => {
+                self.compile_expression(left)?;
+                let end_jump = self.emit(OpCode::JumpIfFalseOrPop(0));
+                self.compile_expression(right)?;
+                let end = self.current_offset();
+                self.patch_jump(end_jump, end);
+            },
+            ExpressionKind::Binary { left, operator: BinaryOperator::Or, right } => {
+                self.compile_expression(left)?;
+                let end_jump = self.emit(OpCode::JumpIfTrueOrPop(0));
+                self.compile_expression(right)?;
+                let end = self.current_offset();
+                self.patch_jump(end_jump, end);
+            },
+            ExpressionKind::Binary { left, operator, right } => {
+                self.compile_expression(left)?;
+                self.compile_expression(right)?;
+                self.emit(OpCode::Binary(*operator));
+            },
+            ExpressionKind::Unary { operator, operand } => {
+                self.compile_expression(operand)?;
+                self.emit(OpCode::Unary(*operator));
+            },
+            ExpressionKind::Call { callee, arguments } => {
+                self.compile_expression(callee)?;
+                for argument in arguments {
+                    self.compile_expression(argument)?;
+                }
+                self.emit(OpCode::Call(arguments.len()));
+            },
+            ExpressionKind::Array(elements) => {
+                for element in elements {
+                    self.compile_expression(element)?;
+                }
+                self.emit(OpCode::BuildArray(elements.len()));
+            },
+            ExpressionKind::Map(entries) => {
+                for (key, value) in entries {
+                    let index = self.add_constant(Value::String(key.clone()));
+                    self.emit(OpCode::Constant(index));
+                    self.compile_expression(value)?;
+                }
+                self.emit(OpCode::BuildMap(entries.len()));
+            },
+            ExpressionKind::Index { object, index } => {
+                self.compile_expression(object)?;
+                self.compile_expression(index)?;
+                self.emit(OpCode::GetIndex);
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parser::Parser;
+
+    fn compile_str(input: &str) -> Result<Arc<FunctionProto>, ScriptError> {
+        let mut parser = Parser::new(input);
+        let statements = parser.parse_program().unwrap();
+        Compiler::compile(&statements)
+    }
+
+    #[test]
+    fn test_compile_globals_and_locals() {
+        let script = compile_str("var x = 1; { var y = x; }").unwrap();
+        let code = &script.chunk.code;
+
+        assert!(code.contains(&OpCode::DefineGlobal(0)));
+        assert!(code.contains(&OpCode::GetGlobal(0)));
+        assert!(code.contains(&OpCode::DefineLocal(0)));
+        assert_eq!(script.local_count, 1);
+        assert_eq!(code.last(), Some(&OpCode::Halt));
+    }
+
+    #[test]
+    fn test_compile_closure_upvalues() {
+        let script = compile_str(r#"
+            fn outer(limit) {
+                fn inner(value) {
+                    return value > limit;
+                }
+                return inner;
+            }
+        "#).unwrap();
+        let outer = &script.chunk.functions[0];
+        let inner = &outer.chunk.functions[0];
+
+        assert_eq!(outer.arity, 1);
+        assert!(outer.upvalues.is_empty());
+        assert_eq!(inner.upvalues, vec![UpvalueRef { is_local: true, index: 0 }]);
+        assert!(inner.chunk.code.contains(&OpCode::GetUpvalue(0)));
+    }
+
+    #[test]
+    fn test_compile_short_circuit_jumps() {
+        let script = compile_str("var x = a && b;").unwrap();
+        let code = &script.chunk.code;
+
+        // a, JumpIfFalseOrPop(hinter b), b
+        assert_eq!(code[0], OpCode::GetGlobal(0));
+        assert_eq!(code[1], OpCode::JumpIfFalseOrPop(3));
+        assert_eq!(code[2], OpCode::GetGlobal(1));
+    }
+
+    #[test]
+    fn test_compile_error_jump_outside_loop() {
+        let result = Compiler::compile(&[StatementKind::Break(None).into()]);
+        assert!(matches!(result, Err(ScriptError { error: RuntimeError::Custom(msg), .. }) if msg == "'break' outside of loop"));
+    }
+}
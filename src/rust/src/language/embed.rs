@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use super::error::RuntimeError;
+use super::interpreter::{NativeCallback, Value};
+use super::natives::{NativeSignature, ParameterKind, TypeSet};
+
+// Umwandlung eines Skriptwerts in einen Rust-Typ, für Parameter von `register_fn`
+pub trait FromValue: Sized {
+    const TYPES: TypeSet;  // Erlaubte Skripttypen, daraus entsteht die Signatur
+    const OPTIONAL: bool = false;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError>;
+}
+
+// Umwandlung eines Rust-Werts in einen Skriptwert, für Rückgabewerte
+pub trait IntoValue {
+    const TYPES: TypeSet;
+
+    fn into_value(self) -> Value;
+}
+
+// Rückgabe einer Funktion für `register_fn`: ein Wert oder ein Ergebnis mit Laufzeitfehler
+pub trait NativeReturn {
+    const TYPES: TypeSet;
+
+    fn into_result(self) -> Result<Value, RuntimeError>;
+}
+
+impl<T: IntoValue> NativeReturn for T {
+    const TYPES: TypeSet = T::TYPES;
+
+    fn into_result(self) -> Result<Value, RuntimeError> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue> NativeReturn for Result<T, RuntimeError> {
+    const TYPES: TypeSet = T::TYPES;
+
+    fn into_result(self) -> Result<Value, RuntimeError> {
+        self.map(IntoValue::into_value)
+    }
+}
+
+fn type_error(expected: &str, value: &Value) -> RuntimeError {
+    RuntimeError::TypeError(format!("Expected {}, got {}", expected, TypeSet::of(value)))
+}
+
+fn integer(value: &Value, min: f64, max: f64) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 && *n >= min && *n <= max => Ok(*n),
+        Value::Number(n) => Err(RuntimeError::TypeError(format!("Expected an integer between {} and {}, got {}", min, max, n))),
+        other => Err(type_error("an integer", other)),
+    }
+}
+
+impl FromValue for Value {
+    const TYPES: TypeSet = TypeSet::ANY;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        Ok(value)
+    }
+}
+
+impl FromValue for f64 {
+    const TYPES: TypeSet = TypeSet::NUMBER;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            other => Err(type_error("a number", &other)),
+        }
+    }
+}
+
+// Ganzzahlen müssen exakt darstellbar sein, 1.5 ist kein gültiges Argument
+macro_rules! integer_from_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                const TYPES: TypeSet = TypeSet::NUMBER;
+
+                fn from_value(value: Value) -> Result<Self, RuntimeError> {
+                    Ok(integer(&value, <$ty>::MIN as f64, <$ty>::MAX as f64)? as $ty)
+                }
+            }
+        )*
+    };
+}
+
+integer_from_value!(i32, i64, u32, u64, usize);
+
+impl FromValue for bool {
+    const TYPES: TypeSet = TypeSet::BOOLEAN;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            other => Err(type_error("a boolean", &other)),
+        }
+    }
+}
+
+impl FromValue for String {
+    const TYPES: TypeSet = TypeSet::STRING;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::String(s) => Ok(s),
+            other => Err(type_error("a string", &other)),
+        }
+    }
+}
+
+// Fehlt das Argument oder ist es null, erhält die Funktion None
+impl<T: FromValue> FromValue for Option<T> {
+    const TYPES: TypeSet = T::TYPES.union(TypeSet::NULL);
+    const OPTIONAL: bool = true;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+// Arrays und Maps werden kopiert; Änderungen wirken nicht auf das Skript zurück
+impl<T: FromValue> FromValue for Vec<T> {
+    const TYPES: TypeSet = TypeSet::ARRAY;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Array(elements) => {
+                let elements = elements.lock().unwrap().clone();
+                elements.into_iter().map(T::from_value).collect()
+            },
+            other => Err(type_error("an array", &other)),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for BTreeMap<String, T> {
+    const TYPES: TypeSet = TypeSet::MAP;
+
+    fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::Map(entries) => {
+                let entries = entries.lock().unwrap().clone();
+                entries.into_iter().map(|(key, value)| Ok((key, T::from_value(value)?))).collect()
+            },
+            other => Err(type_error("a map", &other)),
+        }
+    }
+}
+
+impl IntoValue for Value {
+    const TYPES: TypeSet = TypeSet::ANY;
+
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for () {
+    const TYPES: TypeSet = TypeSet::NULL;
+
+    fn into_value(self) -> Value {
+        Value::Null
+    }
+}
+
+macro_rules! number_into_value {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoValue for $ty {
+                const TYPES: TypeSet = TypeSet::NUMBER;
+
+                fn into_value(self) -> Value {
+                    Value::Number(self as f64)
+                }
+            }
+        )*
+    };
+}
+
+number_into_value!(f64, f32, i32, i64, u32, u64, usize);
+
+impl IntoValue for bool {
+    const TYPES: TypeSet = TypeSet::BOOLEAN;
+
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl IntoValue for String {
+    const TYPES: TypeSet = TypeSet::STRING;
+
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    const TYPES: TypeSet = TypeSet::STRING;
+
+    fn into_value(self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    const TYPES: TypeSet = T::TYPES.union(TypeSet::NULL);
+
+    fn into_value(self) -> Value {
+        self.map(IntoValue::into_value).unwrap_or(Value::Null)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    const TYPES: TypeSet = TypeSet::ARRAY;
+
+    fn into_value(self) -> Value {
+        Value::array(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: IntoValue> IntoValue for BTreeMap<String, T> {
+    const TYPES: TypeSet = TypeSet::MAP;
+
+    fn into_value(self) -> Value {
+        Value::map(self.into_iter().map(|(key, value)| (key, value.into_value())).collect())
+    }
+}
+
+// Rust-Funktion mit beliebigen `FromValue`-Parametern, Args ist das Tupel der Parametertypen.
+// Implementiert für Closures mit bis zu sechs Parametern.
+pub trait IntoNative<Args> {
+    fn parameters() -> Vec<(TypeSet, bool)>;  // Typen und ob der Parameter optional ist
+    fn returns() -> TypeSet;
+    fn into_callback(self) -> NativeCallback;
+}
+
+macro_rules! impl_into_native {
+    ($($arg:ident: $ty:ident),*) => {
+        impl<F, R, $($ty),*> IntoNative<($($ty,)*)> for F
+        where
+            F: Fn($($ty),*) -> R + Send + Sync + 'static,
+            R: NativeReturn,
+            $($ty: FromValue,)*
+        {
+            fn parameters() -> Vec<(TypeSet, bool)> {
+                vec![$(($ty::TYPES, $ty::OPTIONAL)),*]
+            }
+
+            fn returns() -> TypeSet {
+                R::TYPES
+            }
+
+            #[allow(unused_mut, unused_variables)]
+            fn into_callback(self) -> NativeCallback {
+                Arc::new(move |_, args: Vec<Value>| {
+                    // Anzahl und Typen hat die Signatur bereits geprüft
+                    let mut args = args.into_iter();
+                    $(let $arg = $ty::from_value(args.next().unwrap_or(Value::Null))?;)*
+                    (self)($($arg),*).into_result()
+                })
+            }
+        }
+    };
+}
+
+impl_into_native!();
+impl_into_native!(a: A);
+impl_into_native!(a: A, b: B);
+impl_into_native!(a: A, b: B, c: C);
+impl_into_native!(a: A, b: B, c: C, d: D);
+impl_into_native!(a: A, b: B, c: C, d: D, e: E);
+impl_into_native!(a: A, b: B, c: C, d: D, e: E, f: G);
+
+// Signatur aus Name, Doku und Parameternamen der Vorlage sowie den Typen der Funktion.
+// Optional sind nur Parameter, nach denen keine Pflichtparameter mehr folgen.
+pub(super) fn typed_signature<Args, F: IntoNative<Args>>(template: NativeSignature) -> NativeSignature {
+    let parameters = F::parameters();
+    let trailing_optional = parameters.iter().rev().take_while(|(_, optional)| *optional).count();
+    let first_optional = parameters.len() - trailing_optional;
+
+    let mut signature = NativeSignature::new(&template.name).returns(F::returns()).doc(&template.doc);
+    for (i, (types, _)) in parameters.into_iter().enumerate() {
+        let name = template.params.get(i).map(|p| p.name.clone()).unwrap_or_else(|| format!("arg{}", i + 1));
+        signature = if i >= first_optional {
+            signature.optional(&name, types)
+        } else {
+            signature.param(&name, types)
+        };
+    }
+    debug_assert!(signature.params.iter().all(|p| p.kind != ParameterKind::Variadic));
+    signature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::{Interpreter, Parser};
+
+    fn evaluate(interpreter: &mut Interpreter, input: &str) -> Result<Value, String> {
+        let statements = Parser::new(input).parse_program().unwrap();
+        interpreter.interpret(statements).map_err(|error| error.error.to_string())
+    }
+
+    #[test]
+    fn test_register_closures() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn("between", |value: f64, min: f64, max: f64| value >= min && value <= max);
+        interpreter.register_fn(
+            NativeSignature::new("repeat").names(&["text", "count"]).doc("Repeats the text."),
+            |text: String, count: Option<u32>| text.repeat(count.unwrap_or(2) as usize),
+        );
+        interpreter.register_fn("total", |values: Vec<f64>| values.iter().sum::<f64>());
+        interpreter.register_fn("fail", || -> Result<(), RuntimeError> { Err(RuntimeError::Custom("failed".to_string())) });
+
+        assert_eq!(evaluate(&mut interpreter, "between(5, 1, 10);"), Ok(Value::Boolean(true)));
+        assert_eq!(evaluate(&mut interpreter, "repeat(\"ab\");"), Ok(Value::String("abab".to_string())));
+        assert_eq!(evaluate(&mut interpreter, "repeat(\"ab\", 3);"), Ok(Value::String("ababab".to_string())));
+        assert_eq!(evaluate(&mut interpreter, "total([1, 2, 3.5]);"), Ok(Value::Number(6.5)));
+        assert_eq!(evaluate(&mut interpreter, "fail();"), Err("failed".to_string()));
+    }
+
+    #[test]
+    fn test_signature_and_conversion_errors() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn(
+            NativeSignature::new("repeat").names(&["text", "count"]).doc("Repeats the text."),
+            |text: String, count: Option<u32>| text.repeat(count.unwrap_or(2) as usize),
+        );
+        interpreter.register_fn("total", |values: Vec<f64>| values.iter().sum::<f64>());
+
+        let signatures: Vec<String> = interpreter.native_signatures().iter().map(|sig| sig.to_string()).collect();
+        assert!(signatures.contains(&"repeat(text: string, count?: number | null): string".to_string()));
+        assert!(signatures.contains(&"total(arg1: array): number".to_string()));
+
+        assert_eq!(
+            evaluate(&mut interpreter, "repeat(1);"),
+            Err("Type error: repeat: argument 'text' must be string, got number".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "repeat(\"ab\", 1.5);"),
+            Err("Type error: Expected an integer between 0 and 4294967295, got 1.5".to_string())
+        );
+        assert_eq!(
+            evaluate(&mut interpreter, "total([1, \"x\"]);"),
+            Err("Type error: Expected a number, got string".to_string())
+        );
+    }
+}
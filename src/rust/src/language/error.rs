@@ -1,3 +1,6 @@
+use std::time::Duration;
+use super::types::{Position, Span};
+
 #[derive(Debug)]
 pub enum LexerError {
     InvalidCharacter(char, usize, usize),  // Zeichen, Zeile, Spalte
@@ -6,14 +9,18 @@ pub enum LexerError {
     UnterminatedBlockComment(usize, usize), // Zeile, Spalte wo der Kommentar begann
 }
 
+// `expected` und `found` sind lesbare Beschreibungen, z.B. "';'" und "identifier 'x'"
 #[derive(Debug)]
 pub enum ParserError {
-    UnexpectedToken,
-    UnexpectedEOF,
-    ExpectedIdentifier,
-    ExpectedBlock,
-    InvalidExpression,
-    InvalidStatement,
+    UnexpectedToken { expected: String, found: String, span: Span },
+    UnexpectedEOF { expected: String, span: Span },
+    ExpectedIdentifier { found: String, span: Span },
+    ExpectedBlock { found: String, span: Span },
+    InvalidExpression { expected: String, found: String, span: Span },
+    InvalidStatement { expected: String, found: String, span: Span },
+    JumpOutsideLoop { keyword: String, span: Span },  // break/continue ohne passende Schleife
+    UndefinedLabel { label: String, span: Span },
+    Lexer(LexerError),  // Vom Lexer gemeldet, der Parser liest danach weiter
 }
 
 #[derive(Debug)]
@@ -23,10 +30,152 @@ pub enum RuntimeError {
     InvalidOperation,
     TypeError(String),
     InvalidArgumentCount { expected: usize, got: usize },
-    ReturnValue(Box<RuntimeError>),
+    IndexOutOfBounds { index: i64, length: usize },
+    Interrupted { reason: InterruptReason, steps: u64, elapsed: Duration },  // Limit erreicht oder abgebrochen
+    StackOverflow { depth: usize },  // Aufruftiefe beim Überlauf
     Custom(String),
 }
 
+// Warum ein Skript vorzeitig beendet wurde, siehe `ExecutionLimits`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterruptReason {
+    StepLimit(u64),
+    Timeout(Duration),
+    Cancelled,
+}
+
+// Funktion, die ein Fehler auf dem Weg nach außen verlassen hat.
+// `call_site` ist die Stelle beim Aufrufer, von der aus sie aufgerufen wurde.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceFrame {
+    pub function: String,
+    pub call_site: Option<Span>,
+}
+
+// Eintrag im Stacktrace: Funktion und die Stelle, an der sie gerade stand
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackEntry {
+    pub function: String,
+    pub span: Option<Span>,
+}
+
+// Laufzeitfehler samt der Stelle im Quelltext, an der er ausgelöst wurde.
+// Die Position wird beim Zurückreichen vom innersten Ausdruck gesetzt.
+#[derive(Debug)]
+pub struct ScriptError {
+    pub error: RuntimeError,
+    pub span: Option<Span>,
+    pub trace: Vec<TraceFrame>,  // Verlassene Funktionen, von innen nach außen
+}
+
+impl ScriptError {
+    // Setzt die Position nur, wenn noch keine bekannt ist. Nach dem Verlassen einer
+    // Funktion gehört die nächste Position zu deren Aufrufstelle.
+    pub fn or_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        } else if let Some(frame) = self.trace.last_mut() {
+            if frame.call_site.is_none() {
+                frame.call_site = Some(span);
+            }
+        }
+        self
+    }
+
+    pub fn leave_function(mut self, function: &str) -> Self {
+        self.trace.push(TraceFrame {
+            function: function.to_string(),
+            call_site: None,
+        });
+        self
+    }
+
+    // Von der innersten Funktion bis zum Skript selbst
+    pub fn stack_trace(&self) -> Vec<StackEntry> {
+        let mut entries = Vec::with_capacity(self.trace.len() + 1);
+        let mut span = self.span;
+        for frame in &self.trace {
+            entries.push(StackEntry { function: frame.function.clone(), span });
+            span = frame.call_site;
+        }
+        entries.push(StackEntry { function: "<script>".to_string(), span });
+        entries
+    }
+}
+
+impl From<RuntimeError> for ScriptError {
+    fn from(error: RuntimeError) -> Self {
+        ScriptError { error, span: None, trace: Vec::new() }
+    }
+}
+
+impl LexerError {
+    pub fn position(&self) -> Position {
+        let (line, column) = match self {
+            LexerError::InvalidCharacter(_, line, col) => (*line, *col),
+            LexerError::UnterminatedString(line, col) => (*line, *col),
+            LexerError::InvalidNumber(_, line, col) => (*line, *col),
+            LexerError::UnterminatedBlockComment(line, col) => (*line, *col),
+        };
+        Position { line, column }
+    }
+}
+
+impl ParserError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParserError::Lexer(error) => {
+                let start = error.position();
+                Span {
+                    start,
+                    end: Position { line: start.line, column: start.column + 1 },
+                }
+            },
+            ParserError::UnexpectedToken { span, .. }
+            | ParserError::UnexpectedEOF { span, .. }
+            | ParserError::ExpectedIdentifier { span, .. }
+            | ParserError::ExpectedBlock { span, .. }
+            | ParserError::InvalidExpression { span, .. }
+            | ParserError::InvalidStatement { span, .. }
+            | ParserError::JumpOutsideLoop { span, .. }
+            | ParserError::UndefinedLabel { span, .. } => *span,
+        }
+    }
+}
+
+impl ParserError {
+    // Kurzname der Fehlerart, z.B. für die Oberfläche
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::UnexpectedToken { .. } => "UnexpectedToken",
+            ParserError::UnexpectedEOF { .. } => "UnexpectedEOF",
+            ParserError::ExpectedIdentifier { .. } => "ExpectedIdentifier",
+            ParserError::ExpectedBlock { .. } => "ExpectedBlock",
+            ParserError::InvalidExpression { .. } => "InvalidExpression",
+            ParserError::InvalidStatement { .. } => "InvalidStatement",
+            ParserError::JumpOutsideLoop { .. } => "JumpOutsideLoop",
+            ParserError::UndefinedLabel { .. } => "UndefinedLabel",
+            ParserError::Lexer(_) => "LexerError",
+        }
+    }
+}
+
+impl RuntimeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            RuntimeError::UndefinedVariable(_) => "UndefinedVariable",
+            RuntimeError::DivisionByZero => "DivisionByZero",
+            RuntimeError::InvalidOperation => "InvalidOperation",
+            RuntimeError::TypeError(_) => "TypeError",
+            RuntimeError::InvalidArgumentCount { .. } => "InvalidArgumentCount",
+            RuntimeError::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+            RuntimeError::Interrupted { .. } => "Interrupted",
+            RuntimeError::StackOverflow { .. } => "StackOverflow",
+            RuntimeError::Custom(_) => "Custom",
+        }
+    }
+}
+
 impl std::fmt::Display for LexerError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -45,13 +194,26 @@ impl std::fmt::Display for LexerError {
 impl std::fmt::Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            ParserError::UnexpectedToken => write!(f, "Unexpected token"),
-            ParserError::UnexpectedEOF => write!(f, "Unexpected end of file"),
-            ParserError::ExpectedIdentifier => write!(f, "Expected identifier"),
-            ParserError::ExpectedBlock => write!(f, "Expected block"),
-            ParserError::InvalidExpression => write!(f, "Invalid expression"),
-            ParserError::InvalidStatement => write!(f, "Invalid statement"),
+            ParserError::UnexpectedToken { expected, found, .. } =>
+                write!(f, "Expected {}, found {}", expected, found)?,
+            ParserError::UnexpectedEOF { expected, .. } =>
+                write!(f, "Expected {}, found end of file", expected)?,
+            ParserError::ExpectedIdentifier { found, .. } =>
+                write!(f, "Expected identifier, found {}", found)?,
+            ParserError::ExpectedBlock { found, .. } =>
+                write!(f, "Expected block, found {}", found)?,
+            ParserError::InvalidExpression { expected, found, .. } =>
+                write!(f, "Invalid expression: expected {}, found {}", expected, found)?,
+            ParserError::InvalidStatement { expected, found, .. } =>
+                write!(f, "Invalid statement: expected {}, found {}", expected, found)?,
+            ParserError::JumpOutsideLoop { keyword, .. } =>
+                write!(f, "'{}' outside of loop", keyword)?,
+            ParserError::UndefinedLabel { label, .. } =>
+                write!(f, "Undefined loop label '{}'", label)?,
+            // Lexer-Fehler enthalten ihre Position bereits
+            ParserError::Lexer(error) => return write!(f, "{}", error),
         }
+        write!(f, " at {}", self.span().start)
     }
 }
 
@@ -68,14 +230,38 @@ impl std::fmt::Display for RuntimeError {
                 write!(f, "Type error: {}", msg),
             RuntimeError::InvalidArgumentCount { expected, got } => 
                 write!(f, "Invalid argument count: expected {}, got {}", expected, got),
-            RuntimeError::ReturnValue(err) => 
-                write!(f, "Return value error: {}", err),
+            RuntimeError::IndexOutOfBounds { index, length } => 
+                write!(f, "Index {} out of bounds for length {}", index, length),
+            RuntimeError::StackOverflow { depth } =>
+                write!(f, "Stack overflow: too many nested calls (depth {})", depth),
+            RuntimeError::Interrupted { reason, steps, elapsed } =>
+                write!(f, "Execution stopped ({}) after {} steps and {} ms", reason, steps, elapsed.as_millis()),
             RuntimeError::Custom(msg) => 
                 write!(f, "{}", msg),
         }
     }
 }
 
+impl std::fmt::Display for InterruptReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InterruptReason::StepLimit(max_steps) => write!(f, "step limit of {} reached", max_steps),
+            InterruptReason::Timeout(timeout) => write!(f, "timeout of {} ms reached", timeout.as_millis()),
+            InterruptReason::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.error, span.start),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
 impl std::error::Error for LexerError {}
 impl std::error::Error for ParserError {}
-impl std::error::Error for RuntimeError {} 
\ No newline at end of file
+impl std::error::Error for RuntimeError {}
+impl std::error::Error for ScriptError {} 
\ No newline at end of file
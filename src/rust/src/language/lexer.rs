@@ -6,6 +6,7 @@ use super::types::{Token, Position, TokenWithPosition};
 pub struct Lexer<'a> {
     input: Peekable<Chars<'a>>,
     position: Position,
+    token_end: Position,  // Direkt hinter dem zuletzt gelesenen Zeichen
     current_char: Option<char>,
 }
 
@@ -14,6 +15,7 @@ impl<'a> Lexer<'a> {
         let mut lexer = Lexer {
             input: input.chars().peekable(),
             position: Position { line: 1, column: 0 },
+            token_end: Position { line: 1, column: 0 },
             current_char: None,
         };
         lexer.advance();
@@ -21,6 +23,10 @@ impl<'a> Lexer<'a> {
     }
 
     fn advance(&mut self) {
+        self.token_end = Position {
+            line: self.position.line,
+            column: self.position.column + 1,
+        };
         self.current_char = self.input.next();
         if let Some('\n') = self.current_char {
             self.position.line += 1;
@@ -30,6 +36,15 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Token von `start` bis hinter das zuletzt gelesene Zeichen
+    fn token(&self, token: Token, start: Position) -> TokenWithPosition {
+        TokenWithPosition {
+            token,
+            position: start,
+            end: self.token_end,
+        }
+    }
+
     #[allow(dead_code)]
     fn peek(&mut self) -> Option<&char> {
         self.input.peek()
@@ -112,15 +127,17 @@ impl<'a> Lexer<'a> {
             "default" => Token::Default,
             "out" => Token::Out,
             "return" => Token::Return,
+            "break" => Token::Break,
+            "continue" => Token::Continue,
+            "do" => Token::Do,
+            "loop" => Token::Loop,
             "true" => Token::True,
             "false" => Token::False,
+            "null" => Token::Null,
             _ => Token::Identifier(identifier),
         };
         
-        Ok(TokenWithPosition {
-            token,
-            position: current_pos,
-        })
+        Ok(self.token(token, current_pos))
     }
 
     fn read_number(&mut self) -> Result<TokenWithPosition, LexerError> {
@@ -129,7 +146,7 @@ impl<'a> Lexer<'a> {
         let mut has_decimal = false;
 
         while let Some(c) = self.current_char {
-            if c.is_digit(10) {
+            if c.is_ascii_digit() {
                 number.push(c);
                 self.advance();
             } else if c == '.' {
@@ -145,10 +162,7 @@ impl<'a> Lexer<'a> {
         }
 
         match number.parse::<f64>() {
-            Ok(n) => Ok(TokenWithPosition {
-                token: Token::Number(n),
-                position: start_pos,
-            }),
+            Ok(n) => Ok(self.token(Token::Number(n), start_pos)),
             Err(_) => Err(LexerError::InvalidNumber(number, start_pos.line, start_pos.column)),
         }
     }
@@ -162,10 +176,7 @@ impl<'a> Lexer<'a> {
             match c {
                 '"' => {
                     self.advance(); // Skip closing quote
-                    return Ok(TokenWithPosition {
-                        token: Token::String(string),
-                        position: start_pos,
-                    });
+                    return Ok(self.token(Token::String(string), start_pos));
                 }
                 '\\' => {
                     self.advance();
@@ -212,10 +223,7 @@ impl<'a> Lexer<'a> {
                                 continue;
                             }
                             _ => {
-                                return Ok(TokenWithPosition {
-                                    token: Token::Divide,
-                                    position: current_pos,
-                                });
+                                return Ok(self.token(Token::Divide, current_pos));
                             }
                         }
                     }
@@ -226,93 +234,57 @@ impl<'a> Lexer<'a> {
                     '"' => return self.read_string(),
                     '+' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::Plus,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::Plus, current_pos))
                     }
                     '-' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::Minus,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::Minus, current_pos))
                     }
                     '*' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::Multiply,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::Multiply, current_pos))
                     }
                     '=' => {
                         self.advance();
                         if let Some('=') = self.current_char {
                             self.advance();
-                            return Ok(TokenWithPosition {
-                                token: Token::Equals,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::Equals, current_pos))
                         } else {
-                            return Ok(TokenWithPosition {
-                                token: Token::Assign,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::Assign, current_pos))
                         }
                     }
                     '!' => {
                         self.advance();
                         if let Some('=') = self.current_char {
                             self.advance();
-                            return Ok(TokenWithPosition {
-                                token: Token::NotEquals,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::NotEquals, current_pos))
                         } else {
-                            return Ok(TokenWithPosition {
-                                token: Token::Not,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::Not, current_pos))
                         }
                     }
                     '>' => {
                         self.advance();
                         if let Some('=') = self.current_char {
                             self.advance();
-                            return Ok(TokenWithPosition {
-                                token: Token::GreaterEquals,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::GreaterEquals, current_pos))
                         } else {
-                            return Ok(TokenWithPosition {
-                                token: Token::Greater,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::Greater, current_pos))
                         }
                     }
                     '<' => {
                         self.advance();
                         if let Some('=') = self.current_char {
                             self.advance();
-                            return Ok(TokenWithPosition {
-                                token: Token::LessEquals,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::LessEquals, current_pos))
                         } else {
-                            return Ok(TokenWithPosition {
-                                token: Token::Less,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::Less, current_pos))
                         }
                     }
                     '&' => {
                         self.advance();
                         if let Some('&') = self.current_char {
                             self.advance();
-                            return Ok(TokenWithPosition {
-                                token: Token::And,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::And, current_pos))
                         } else {
                             return Err(LexerError::InvalidCharacter('&', current_pos.line, current_pos.column))
                         }
@@ -321,73 +293,66 @@ impl<'a> Lexer<'a> {
                         self.advance();
                         if let Some('|') = self.current_char {
                             self.advance();
-                            return Ok(TokenWithPosition {
-                                token: Token::Or,
-                                position: current_pos,
-                            })
+                            return Ok(self.token(Token::Or, current_pos))
                         } else {
                             return Err(LexerError::InvalidCharacter('|', current_pos.line, current_pos.column))
                         }
                     }
                     '(' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::LeftParen,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::LeftParen, current_pos))
                     }
                     ')' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::RightParen,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::RightParen, current_pos))
                     }
                     '{' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::LeftBrace,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::LeftBrace, current_pos))
                     }
                     '}' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::RightBrace,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::RightBrace, current_pos))
+                    }
+                    '[' => {
+                        self.advance();
+                        return Ok(self.token(Token::LeftBracket, current_pos))
+                    }
+                    ']' => {
+                        self.advance();
+                        return Ok(self.token(Token::RightBracket, current_pos))
                     }
                     ',' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::Comma,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::Comma, current_pos))
+                    }
+                    '.' => {
+                        self.advance();
+                        return Ok(self.token(Token::Dot, current_pos))
                     }
                     ':' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::Colon,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::Colon, current_pos))
                     }
                     ';' => {
                         self.advance();
-                        return Ok(TokenWithPosition {
-                            token: Token::Semicolon,
-                            position: current_pos,
-                        })
+                        return Ok(self.token(Token::Semicolon, current_pos))
+                    }
+                    _ => {
+                        // Zeichen überspringen, damit nach dem Fehler weitergelesen werden kann
+                        self.advance();
+                        return Err(LexerError::InvalidCharacter(
+                            c,
+                            current_pos.line,
+                            current_pos.column,
+                        ))
                     }
-                    _ => return Err(LexerError::InvalidCharacter(
-                        c,
-                        current_pos.line,
-                        current_pos.column,
-                    )),
                 }
             } else {
                 return Ok(TokenWithPosition {
                     token: Token::EOF,
                     position: current_pos,
+                    end: current_pos,
                 });
             }
         }
@@ -397,6 +362,7 @@ impl<'a> Lexer<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::types::Span;
 
     #[test]
     fn test_keywords() {
@@ -424,6 +390,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_loop_control_keywords() {
+        let input = "break continue do loop";
+        let mut lexer = Lexer::new(input);
+
+        let expected = vec![
+            Token::Break,
+            Token::Continue,
+            Token::Do,
+            Token::Loop,
+            Token::EOF,
+        ];
+
+        for expected_token in expected {
+            let result = lexer.next_token().unwrap();
+            assert_eq!(result.token, expected_token);
+        }
+    }
+
     #[test]
     fn test_identifiers() {
         let input = "abc x123 _test test_var";
@@ -444,6 +429,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_numbers() {
         let input = "42 3.14 0.123 42.0";
         let mut lexer = Lexer::new(input);
@@ -531,6 +517,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_brackets() {
+        let input = "a[0] = [1, 2];";
+        let mut lexer = Lexer::new(input);
+
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::LeftBracket,
+            Token::Number(0.0),
+            Token::RightBracket,
+            Token::Assign,
+            Token::LeftBracket,
+            Token::Number(1.0),
+            Token::Comma,
+            Token::Number(2.0),
+            Token::RightBracket,
+            Token::Semicolon,
+            Token::EOF,
+        ];
+
+        for expected_token in expected {
+            let result = lexer.next_token().unwrap();
+            assert_eq!(result.token, expected_token);
+        }
+    }
+
+    #[test]
+    fn test_map_literal_and_field_access() {
+        let input = "{ \"pid\": 1, name: 2 }.name";
+        let mut lexer = Lexer::new(input);
+
+        let expected = vec![
+            Token::LeftBrace,
+            Token::String("pid".to_string()),
+            Token::Colon,
+            Token::Number(1.0),
+            Token::Comma,
+            Token::Identifier("name".to_string()),
+            Token::Colon,
+            Token::Number(2.0),
+            Token::RightBrace,
+            Token::Dot,
+            Token::Identifier("name".to_string()),
+            Token::EOF,
+        ];
+
+        for expected_token in expected {
+            let result = lexer.next_token().unwrap();
+            assert_eq!(result.token, expected_token);
+        }
+    }
+
     #[test]
     fn test_complex_expression() {
         let input = "if (x > 5) { print(\"Hello\"); }";
@@ -567,6 +605,18 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_continue_after_invalid_character() {
+        let input = "x @# y";
+        let mut lexer = Lexer::new(input);
+
+        assert_eq!(lexer.next_token().unwrap().token, Token::Identifier("x".to_string()));
+        assert!(matches!(lexer.next_token(), Err(LexerError::InvalidCharacter('@', 1, 3))));
+        assert!(matches!(lexer.next_token(), Err(LexerError::InvalidCharacter('#', 1, 4))));
+        assert_eq!(lexer.next_token().unwrap().token, Token::Identifier("y".to_string()));
+        assert_eq!(lexer.next_token().unwrap().token, Token::EOF);
+    }
+
     #[test]
     fn test_error_unterminated_string() {
         let input = "\"unterminated";
@@ -656,4 +706,29 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap().token, Token::Semicolon);
         assert_eq!(lexer.next_token().unwrap().token, Token::EOF);
     }
-} 
\ No newline at end of file
+    #[test]
+    fn test_token_spans() {
+        let input = "var name = \"aion\";\n  out(name);";
+        let mut lexer = Lexer::new(input);
+
+        let span = |line, start, end| Span {
+            start: Position { line, column: start },
+            end: Position { line, column: end },
+        };
+        let expected = vec![
+            (Token::Var, span(1, 1, 4)),
+            (Token::Identifier("name".to_string()), span(1, 5, 9)),
+            (Token::Assign, span(1, 10, 11)),
+            (Token::String("aion".to_string()), span(1, 12, 18)),
+            (Token::Semicolon, span(1, 18, 19)),
+            (Token::Out, span(2, 3, 6)),
+            (Token::LeftParen, span(2, 6, 7)),
+        ];
+
+        for (expected_token, expected_span) in expected {
+            let result = lexer.next_token().unwrap();
+            assert_eq!(result.token, expected_token);
+            assert_eq!(result.span(), expected_span);
+        }
+    }
+}
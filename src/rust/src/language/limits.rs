@@ -0,0 +1,338 @@
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use super::error::{InterruptReason, RuntimeError};
+
+// Uhr und Abbruch-Flag werden nur alle paar Schritte geprüft, das Schrittlimit immer
+const CHECK_INTERVAL: u64 = 1024;
+
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+// Der Tree-Walker bildet Skriptaufrufe auf den nativen Stack ab. Die Standardgröße
+// hängt von Plattform und Aufrufer ab (unter Windows oft nur 1 MB), daher läuft er auf
+// einem eigenen Thread mit dieser Größe. Jeder aufrufende Thread bekommt einen solchen
+// Thread beim ersten Lauf und behält ihn, statt für jeden Lauf einen neuen zu starten.
+pub(super) const TREE_WALKER_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+// Der Rest bleibt für native Funktionen und alles, was nach der letzten Prüfung läuft
+const NATIVE_STACK_BUDGET: usize = TREE_WALKER_STACK_SIZE - 1024 * 1024;
+
+type CancelCallback = Box<dyn FnOnce() + Send>;
+
+// Von außen auslösbarer Abbruch. Klone teilen denselben Zustand,
+// daher kann ein anderer Thread das laufende Skript stoppen.
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<CancellationState>);
+
+#[derive(Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    next_listener: AtomicU64,
+    listeners: Mutex<Vec<(u64, CancelCallback)>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+        let listeners = std::mem::take(&mut *self.0.listeners.lock().unwrap());
+        for (_, listener) in listeners {
+            listener();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Ruft `listener` beim Abbruch auf, damit blockierende native Funktionen sofort
+    // aufwachen. Ist schon abgebrochen, geschieht das direkt. Gilt, bis der
+    // zurückgegebene Wert fällt.
+    pub fn on_cancel(&self, listener: impl FnOnce() + Send + 'static) -> CancelListener {
+        let id = self.0.next_listener.fetch_add(1, Ordering::Relaxed);
+        let mut listeners = self.0.listeners.lock().unwrap();
+        if self.is_cancelled() {
+            drop(listeners);
+            listener();
+        } else {
+            listeners.push((id, Box::new(listener)));
+        }
+        CancelListener { token: self.clone(), id }
+    }
+}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("CancellationToken").field(&self.is_cancelled()).finish()
+    }
+}
+
+pub struct CancelListener {
+    token: CancellationToken,
+    id: u64,
+}
+
+impl Drop for CancelListener {
+    fn drop(&mut self) {
+        self.token.0.listeners.lock().unwrap().retain(|(id, _)| *id != self.id);
+    }
+}
+
+// Grenzen für einen Skriptlauf. Ein Schritt ist im Tree-Walker ein ausgewertetes
+// Statement bzw. ein Ausdruck, in der VM eine Instruktion.
+#[derive(Debug, Clone)]
+pub struct ExecutionLimits {
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub cancellation: Option<CancellationToken>,
+    pub max_call_depth: Option<usize>,  // None: nur durch den Speicher begrenzt (VM)
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        ExecutionLimits {
+            max_steps: None,
+            timeout: None,
+            cancellation: None,
+            max_call_depth: Some(DEFAULT_MAX_CALL_DEPTH),
+        }
+    }
+}
+
+// Fortschritt des aktuellen Laufs, wird bei jedem `interpret` neu gestartet
+pub(super) struct Budget {
+    limits: ExecutionLimits,
+    steps: u64,
+    started: Instant,
+    depth: usize,
+}
+
+impl Budget {
+    pub fn new(limits: ExecutionLimits) -> Self {
+        Budget {
+            limits,
+            steps: 0,
+            started: Instant::now(),
+            depth: 0,
+        }
+    }
+
+    pub fn limits(&self) -> &ExecutionLimits {
+        &self.limits
+    }
+
+    pub fn restart(&mut self) {
+        self.steps = 0;
+        self.started = Instant::now();
+        self.depth = 0;
+    }
+
+    pub fn step(&mut self) -> Result<(), RuntimeError> {
+        if let Some(max_steps) = self.limits.max_steps {
+            if self.steps >= max_steps {
+                return Err(self.interrupted(InterruptReason::StepLimit(max_steps)));
+            }
+        }
+        if self.steps.is_multiple_of(CHECK_INTERVAL) {
+            self.check_interrupt()?;
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    // Abbruch und Zeitlimit ohne Schritt, z.B. während eine native Funktion wartet
+    pub fn check_interrupt(&self) -> Result<(), RuntimeError> {
+        if let Some(token) = &self.limits.cancellation {
+            if token.is_cancelled() {
+                return Err(self.interrupted(InterruptReason::Cancelled));
+            }
+        }
+        if let Some(timeout) = self.limits.timeout {
+            if self.started.elapsed() >= timeout {
+                return Err(self.interrupted(InterruptReason::Timeout(timeout)));
+            }
+        }
+        Ok(())
+    }
+
+    // Zeit bis zum Zeitlimit, falls eines gesetzt ist
+    pub fn remaining_time(&self) -> Option<Duration> {
+        self.limits.timeout.map(|timeout| timeout.saturating_sub(self.started.elapsed()))
+    }
+
+    pub fn enter_call(&mut self) -> Result<(), RuntimeError> {
+        if let Some(max_call_depth) = self.limits.max_call_depth {
+            if self.depth >= max_call_depth {
+                return Err(RuntimeError::StackOverflow { depth: self.depth });
+            }
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub fn exit_call(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // Nach einem Fehler in der VM sind deren Frames verworfen, ohne einzeln zurückzukehren
+    pub fn unwind_to(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    // Schützt vor einem echten Stacküberlauf, unabhängig von `max_call_depth`.
+    // Gemessen wird nur auf dem Tree-Walker-Thread, dort ist die Größe bekannt.
+    pub fn check_native_stack(&self) -> Result<(), RuntimeError> {
+        if let Some(base) = STACK_BASE.get() {
+            if base.abs_diff(stack_position()) > NATIVE_STACK_BUDGET {
+                return Err(RuntimeError::StackOverflow { depth: self.depth });
+            }
+        }
+        Ok(())
+    }
+
+    fn interrupted(&self, reason: InterruptReason) -> RuntimeError {
+        RuntimeError::Interrupted {
+            reason,
+            steps: self.steps,
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+type TreeWalkerJob = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    // Tree-Walker-Thread des aktuellen Threads; endet, wenn dieser endet
+    static TREE_WALKER: RefCell<Option<Sender<TreeWalkerJob>>> = const { RefCell::new(None) };
+    // Stackadresse am Anfang des Tree-Walker-Threads, nur dort gesetzt
+    static STACK_BASE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+// Meldet das Ende eines Auftrags, auch wenn er verworfen statt ausgeführt wird
+struct Finished(SyncSender<()>);
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+pub(super) fn on_tree_walker_thread() -> bool {
+    STACK_BASE.get().is_some()
+}
+
+// Führt `job` auf dem Tree-Walker-Thread des aktuellen Threads aus und wartet darauf.
+// Eine Panik im Auftrag wird hier fortgesetzt.
+pub(super) fn run_on_tree_walker<R: Send>(job: impl FnOnce() -> R + Send) -> Result<R, RuntimeError> {
+    let mut outcome = None;
+    let slot = &mut outcome;
+    let (finished, done) = mpsc::sync_channel(1);
+    let finished = Finished(finished);
+    let task: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+        let _finished = finished;
+        *slot = Some(panic::catch_unwind(AssertUnwindSafe(job)));
+    });
+    // SAFETY: Der Auftrag leiht `job` und `outcome` aus diesem Aufruf. Wir kehren erst
+    // zurück, wenn er ausgeführt oder verworfen wurde (`done`), danach greift er auf
+    // nichts mehr davon zu.
+    let task = unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + '_>, TreeWalkerJob>(task) };
+
+    let sent = TREE_WALKER.with(|walker| {
+        let mut walker = walker.borrow_mut();
+        if walker.is_none() {
+            *walker = Some(start_tree_walker()?);
+        }
+        walker.as_ref().unwrap().send(task).map_err(|_| {
+            *walker = None;
+            std::io::Error::other("thread has stopped")
+        })
+    });
+    let _ = done.recv();
+    sent.map_err(|error| RuntimeError::Custom(format!("Cannot start the interpreter thread: {}", error)))?;
+
+    match outcome {
+        Some(Ok(result)) => Ok(result),
+        Some(Err(payload)) => panic::resume_unwind(payload),
+        None => unreachable!("tree walker job finished without a result"),
+    }
+}
+
+fn start_tree_walker() -> std::io::Result<Sender<TreeWalkerJob>> {
+    let (sender, jobs) = mpsc::channel::<TreeWalkerJob>();
+    std::thread::Builder::new()
+        .name("tree-walker".to_string())
+        .stack_size(TREE_WALKER_STACK_SIZE)
+        .spawn(move || {
+            STACK_BASE.set(Some(stack_position()));
+            for job in jobs {
+                job();
+            }
+        })?;
+    Ok(sender)
+}
+
+#[inline(never)]
+pub(super) fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_wakes_listeners() {
+        let token = CancellationToken::new();
+        let (sender, receiver) = mpsc::channel();
+        let first = sender.clone();
+        let _listener = token.on_cancel(move || first.send("first").unwrap());
+        // Ein aufgegebener Listener wird nicht mehr gerufen
+        let second = sender.clone();
+        drop(token.on_cancel(move || second.send("dropped").unwrap()));
+
+        token.cancel();
+        assert_eq!(receiver.try_recv(), Ok("first"));
+        assert!(receiver.try_recv().is_err());
+
+        // Nach dem Abbruch sofort
+        let _late = token.on_cancel(move || sender.send("late").unwrap());
+        assert_eq!(receiver.try_recv(), Ok("late"));
+    }
+
+    #[test]
+    fn test_tree_walker_thread_is_reused() {
+        let caller = std::thread::current().id();
+        let first = run_on_tree_walker(|| std::thread::current().id()).unwrap();
+        let second = run_on_tree_walker(|| std::thread::current().id()).unwrap();
+        assert_ne!(first, caller);
+        assert_eq!(first, second);
+        assert!(!on_tree_walker_thread());
+        assert!(run_on_tree_walker(on_tree_walker_thread).unwrap());
+    }
+
+    #[test]
+    fn test_tree_walker_job_borrows_from_caller() {
+        let mut values = vec![1, 2];
+        run_on_tree_walker(|| values.push(3)).unwrap();
+        assert_eq!(values, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_tree_walker_panic_reaches_caller() {
+        let result = panic::catch_unwind(|| run_on_tree_walker(|| panic!("boom")));
+        assert!(result.is_err());
+        // Der Thread läuft danach weiter
+        assert_eq!(run_on_tree_walker(|| 7).unwrap(), 7);
+    }
+}
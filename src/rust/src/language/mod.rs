@@ -4,6 +4,13 @@ pub mod types;
 pub mod ast;
 pub mod parser;
 pub mod interpreter;
+pub mod bytecode;
+pub mod compiler;
+pub mod vm;
+pub mod limits;
+pub mod natives;
+pub mod embed;
+mod collector;
 
 // Re-export wichtiger Komponenten
 pub use lexer::*;
@@ -11,4 +18,10 @@ pub use error::*;
 pub use types::*;
 pub use ast::*;
 pub use parser::*;
-pub use interpreter::*; 
\ No newline at end of file
+pub use interpreter::*;
+pub use bytecode::*;
+pub use compiler::*;
+pub use vm::*;
+pub use limits::*;
+pub use natives::*;
+pub use embed::*; 
\ No newline at end of file
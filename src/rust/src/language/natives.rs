@@ -0,0 +1,239 @@
+use super::error::RuntimeError;
+use super::interpreter::Value;
+
+// Menge erlaubter Typen für Parameter und Rückgabewerte, z.B. `TypeSet::MAP | TypeSet::NULL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeSet(u8);
+
+impl TypeSet {
+    pub const NUMBER: TypeSet = TypeSet(1);
+    pub const STRING: TypeSet = TypeSet(1 << 1);
+    pub const BOOLEAN: TypeSet = TypeSet(1 << 2);
+    pub const NULL: TypeSet = TypeSet(1 << 3);
+    pub const ARRAY: TypeSet = TypeSet(1 << 4);
+    pub const MAP: TypeSet = TypeSet(1 << 5);
+    pub const FUNCTION: TypeSet = TypeSet(1 << 6);
+    pub const ANY: TypeSet = TypeSet(0x7f);
+
+    const NAMES: [(TypeSet, &'static str); 7] = [
+        (TypeSet::NUMBER, "number"),
+        (TypeSet::STRING, "string"),
+        (TypeSet::BOOLEAN, "boolean"),
+        (TypeSet::NULL, "null"),
+        (TypeSet::ARRAY, "array"),
+        (TypeSet::MAP, "map"),
+        (TypeSet::FUNCTION, "function"),
+    ];
+
+    pub fn of(value: &Value) -> TypeSet {
+        match value {
+            Value::Number(_) => TypeSet::NUMBER,
+            Value::String(_) => TypeSet::STRING,
+            Value::Boolean(_) => TypeSet::BOOLEAN,
+            Value::Null => TypeSet::NULL,
+            Value::Array(_) => TypeSet::ARRAY,
+            Value::Map(_) => TypeSet::MAP,
+            Value::NativeFunction(_) | Value::Function(_) | Value::Closure(_) => TypeSet::FUNCTION,
+        }
+    }
+
+    // Wie `|`, aber auch in Konstanten verwendbar
+    pub const fn union(self, other: TypeSet) -> TypeSet {
+        TypeSet(self.0 | other.0)
+    }
+
+    pub fn contains(self, other: TypeSet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn accepts(self, value: &Value) -> bool {
+        self.contains(TypeSet::of(value))
+    }
+}
+
+impl std::ops::BitOr for TypeSet {
+    type Output = TypeSet;
+
+    fn bitor(self, other: TypeSet) -> TypeSet {
+        TypeSet(self.0 | other.0)
+    }
+}
+
+// Schreibweise wie in der Signaturhilfe: "any" oder "map | null"
+impl std::fmt::Display for TypeSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if *self == TypeSet::ANY {
+            return write!(f, "any");
+        }
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(types, _)| self.contains(*types))
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", names.join(" | "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterKind {
+    Required,
+    Optional,  // Fehlt er, erhält die Funktion null
+    Variadic,  // Nimmt alle restlichen Argumente auf
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub types: TypeSet,
+    pub kind: ParameterKind,
+}
+
+// Beschreibung einer nativen Funktion. Der Interpreter prüft Anzahl und Typen
+// der Argumente vor dem Aufruf, der Editor nutzt sie für Vervollständigung.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NativeSignature {
+    pub name: String,
+    pub params: Vec<Parameter>,
+    pub returns: TypeSet,
+    pub doc: String,
+}
+
+impl NativeSignature {
+    pub fn new(name: &str) -> Self {
+        NativeSignature {
+            name: name.to_string(),
+            params: Vec::new(),
+            returns: TypeSet::NULL,
+            doc: String::new(),
+        }
+    }
+
+    pub fn param(self, name: &str, types: TypeSet) -> Self {
+        debug_assert!(self.params.iter().all(|p| p.kind == ParameterKind::Required), "required after optional");
+        self.with_param(name, types, ParameterKind::Required)
+    }
+
+    pub fn optional(self, name: &str, types: TypeSet) -> Self {
+        debug_assert!(self.params.iter().all(|p| p.kind != ParameterKind::Variadic), "optional after variadic");
+        self.with_param(name, types, ParameterKind::Optional)
+    }
+
+    pub fn variadic(self, name: &str, types: TypeSet) -> Self {
+        debug_assert!(self.params.iter().all(|p| p.kind != ParameterKind::Variadic), "second variadic");
+        self.with_param(name, types, ParameterKind::Variadic)
+    }
+
+    // Nur Parameternamen, für `register_fn`; Typen und Art kommen dort aus der Closure
+    pub fn names(self, names: &[&str]) -> Self {
+        names.iter().fold(self, |signature, name| signature.param(name, TypeSet::ANY))
+    }
+
+    pub fn returns(mut self, types: TypeSet) -> Self {
+        self.returns = types;
+        self
+    }
+
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.doc = doc.to_string();
+        self
+    }
+
+    fn with_param(mut self, name: &str, types: TypeSet, kind: ParameterKind) -> Self {
+        self.params.push(Parameter { name: name.to_string(), types, kind });
+        self
+    }
+
+    fn required_count(&self) -> usize {
+        self.params.iter().filter(|p| p.kind == ParameterKind::Required).count()
+    }
+
+    fn is_variadic(&self) -> bool {
+        self.params.iter().any(|p| p.kind == ParameterKind::Variadic)
+    }
+
+    // Prüft die Argumente und ergänzt fehlende optionale mit null
+    pub fn bind(&self, mut args: Vec<Value>) -> Result<Vec<Value>, RuntimeError> {
+        let required = self.required_count();
+        let fixed = self.params.iter().filter(|p| p.kind != ParameterKind::Variadic).count();
+        if args.len() < required {
+            return Err(RuntimeError::InvalidArgumentCount { expected: required, got: args.len() });
+        }
+        if args.len() > fixed && !self.is_variadic() {
+            return Err(RuntimeError::InvalidArgumentCount { expected: fixed, got: args.len() });
+        }
+
+        for (i, arg) in args.iter().enumerate() {
+            let param = &self.params[i.min(self.params.len() - 1)];
+            let omitted = param.kind == ParameterKind::Optional && matches!(arg, Value::Null);
+            if !omitted && !param.types.accepts(arg) {
+                return Err(RuntimeError::TypeError(format!(
+                    "{}: argument '{}' must be {}, got {}",
+                    self.name, param.name, param.types, TypeSet::of(arg)
+                )));
+            }
+        }
+
+        if args.len() < fixed {
+            args.resize(fixed, Value::Null);
+        }
+        Ok(args)
+    }
+}
+
+impl From<&str> for NativeSignature {
+    fn from(name: &str) -> Self {
+        NativeSignature::new(name)
+    }
+}
+
+// Kopfzeile für die Signaturhilfe, z.B. "round(value: number, digits?: number): number"
+impl std::fmt::Display for NativeSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self.params
+            .iter()
+            .map(|p| match p.kind {
+                ParameterKind::Required => format!("{}: {}", p.name, p.types),
+                ParameterKind::Optional => format!("{}?: {}", p.name, p.types),
+                ParameterKind::Variadic => format!("...{}: {}", p.name, p.types),
+            })
+            .collect();
+        write!(f, "{}({}): {}", self.name, params.join(", "), self.returns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature() -> NativeSignature {
+        NativeSignature::new("format")
+            .param("template", TypeSet::STRING)
+            .optional("width", TypeSet::NUMBER)
+            .variadic("values", TypeSet::ANY)
+            .returns(TypeSet::STRING | TypeSet::NULL)
+    }
+
+    #[test]
+    fn test_signature_display() {
+        assert_eq!(signature().to_string(), "format(template: string, width?: number, ...values: any): string | null");
+    }
+
+    #[test]
+    fn test_bind_arguments() {
+        let sig = signature();
+        assert_eq!(sig.bind(vec![Value::String("x".to_string())]).unwrap().len(), 2);
+        // Optionale Parameter dürfen auch ausdrücklich null sein
+        assert_eq!(sig.bind(vec![Value::String("x".to_string()), Value::Null]).unwrap().len(), 2);
+        assert_eq!(sig.bind(vec![Value::String("x".to_string()), Value::Number(1.0), Value::Boolean(true), Value::Null]).unwrap().len(), 4);
+        assert!(matches!(sig.bind(vec![]), Err(RuntimeError::InvalidArgumentCount { expected: 1, got: 0 })));
+
+        let error = sig.bind(vec![Value::String("x".to_string()), Value::Boolean(true)]).unwrap_err();
+        assert_eq!(error.to_string(), "Type error: format: argument 'width' must be number, got boolean");
+
+        let fixed = NativeSignature::new("keys").param("map", TypeSet::MAP);
+        assert!(matches!(
+            fixed.bind(vec![Value::map(Default::default()), Value::Null]),
+            Err(RuntimeError::InvalidArgumentCount { expected: 1, got: 2 })
+        ));
+    }
+}
@@ -1,30 +1,68 @@
-use super::ast::{Expression, Statement, BinaryOperator};
+use super::ast::{Expression, ExpressionKind, Statement, StatementKind, BinaryOperator, UnaryOperator};
 use super::error::ParserError;
-use super::types::{Token, TokenWithPosition};
+use super::types::{Token, TokenWithPosition, Position, Span};
 use super::lexer::Lexer;
 
+// Umschließende Konstrukte, aus denen `break`/`continue` herausspringen können
+enum JumpTarget {
+    Loop(Option<String>),
+    Switch,
+}
+
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     current_token: Option<TokenWithPosition>,
     peek_token: Option<TokenWithPosition>,
+    previous_end: Position,  // Ende des zuletzt konsumierten Tokens
+    jump_targets: Vec<JumpTarget>,
+    diagnostics: Vec<ParserError>,
+}
+
+// Ergebnis von `Parser::parse_with_recovery`: alle fehlerfrei geparsten Statements
+// und sämtliche Lexer- und Parser-Fehler, nach Position sortiert
+#[derive(Debug)]
+pub struct ParseOutput {
+    pub statements: Vec<Statement>,
+    pub diagnostics: Vec<ParserError>,
+}
+
+impl ParseOutput {
+    pub fn has_errors(&self) -> bool {
+        !self.diagnostics.is_empty()
+    }
 }
 
 impl<'a> Parser<'a> {
     pub fn new(input: &'a str) -> Self {
-        let mut lexer = Lexer::new(input);
-        let current_token = lexer.next_token().ok();
-        let peek_token = lexer.next_token().ok();
-        
-        Parser {
-            lexer,
-            current_token,
-            peek_token,
+        let mut parser = Parser {
+            lexer: Lexer::new(input),
+            current_token: None,
+            peek_token: None,
+            previous_end: Position { line: 1, column: 1 },
+            jump_targets: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+        parser.current_token = Some(parser.read_token());
+        parser.peek_token = Some(parser.read_token());
+        parser
+    }
+
+    // Nächstes gültiges Token; Lexer-Fehler werden gesammelt und übersprungen
+    fn read_token(&mut self) -> TokenWithPosition {
+        loop {
+            match self.lexer.next_token() {
+                Ok(token) => return token,
+                Err(error) => self.diagnostics.push(ParserError::Lexer(error)),
+            }
         }
     }
 
     fn advance_token(&mut self) {
+        if let Some(token) = &self.current_token {
+            self.previous_end = token.end;
+        }
         self.current_token = self.peek_token.take();
-        self.peek_token = self.lexer.next_token().ok();
+        self.peek_token = Some(self.read_token());
     }
 
     fn expect_token(&mut self, expected: Token) -> Result<(), ParserError> {
@@ -34,27 +72,160 @@ impl<'a> Parser<'a> {
                 return Ok(());
             }
         }
-        Err(ParserError::UnexpectedToken)
+        Err(self.unexpected(&expected.to_string()))
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, ParserError> {
+        if let Some(TokenWithPosition { token: Token::Identifier(name), .. }) = &self.current_token {
+            let name = name.clone();
+            self.advance_token();
+            Ok(name)
+        } else {
+            Err(ParserError::ExpectedIdentifier {
+                found: self.found(),
+                span: self.current_span(),
+            })
+        }
+    }
+
+    // Anfang des aktuellen Tokens, an dem ein neuer Knoten beginnt
+    fn start_position(&self) -> Position {
+        match &self.current_token {
+            Some(token) => token.position,
+            None => self.previous_end,
+        }
+    }
+
+    // Bereich von `start` bis hinter das zuletzt konsumierte Token
+    fn span_from(&self, start: Position) -> Span {
+        Span {
+            start,
+            end: self.previous_end,
+        }
+    }
+
+    fn current_span(&self) -> Span {
+        match &self.current_token {
+            Some(token) => token.span(),
+            None => Span {
+                start: self.previous_end,
+                end: self.previous_end,
+            },
+        }
+    }
+
+    // Beschreibung des aktuellen Tokens für Fehlermeldungen
+    fn found(&self) -> String {
+        match &self.current_token {
+            Some(token) => token.token.to_string(),
+            None => Token::EOF.to_string(),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParserError {
+        match &self.current_token {
+            Some(TokenWithPosition { token: Token::EOF, .. }) | None => ParserError::UnexpectedEOF {
+                expected: expected.to_string(),
+                span: self.current_span(),
+            },
+            Some(token) => ParserError::UnexpectedToken {
+                expected: expected.to_string(),
+                found: token.token.to_string(),
+                span: token.span(),
+            },
+        }
+    }
+
+    // Überspringt einen Block samt verschachtelter Blöcke bis hinter die schließende Klammer
+    fn skip_block(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = &self.current_token {
+            match token.token {
+                Token::EOF => return,
+                Token::LeftBrace => depth += 1,
+                Token::RightBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance_token();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.advance_token();
+        }
     }
 
-    // Hauptparsing-Methode
+    fn is_at_end(&self) -> bool {
+        matches!(self.current_token, None | Some(TokenWithPosition { token: Token::EOF, .. }))
+    }
+
+    // Hauptparsing-Methode; liefert den ersten Fehler, falls es welche gab
     pub fn parse_program(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let output = self.parse_with_recovery();
+        match output.diagnostics.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(output.statements),
+        }
+    }
+
+    // Parst das ganze Programm und setzt nach Fehlern an der nächsten Statement-Grenze wieder auf
+    pub fn parse_with_recovery(&mut self) -> ParseOutput {
         let mut statements = Vec::new();
         
-        while let Some(token) = &self.current_token {
-            if matches!(token.token, Token::EOF) {
-                break;
+        while !self.is_at_end() {
+            if let Some(stmt) = self.parse_statement_or_recover() {
+                statements.push(stmt);
             }
-            let stmt = self.parse_statement()?;
-            statements.push(stmt);
         }
         
-        Ok(statements)
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        diagnostics.sort_by_key(|error| error.span().start);
+        ParseOutput { statements, diagnostics }
+    }
+
+    fn parse_statement_or_recover(&mut self) -> Option<Statement> {
+        let start = self.start_position();
+        match self.parse_statement() {
+            Ok(stmt) => Some(stmt),
+            Err(error) => {
+                self.diagnostics.push(error);
+                self.synchronize();
+                // Ohne Fortschritt würde derselbe Fehler endlos erneut gemeldet
+                if self.start_position() == start && !self.is_at_end() {
+                    self.advance_token();
+                }
+                None
+            }
+        }
+    }
+
+    // Panic-Mode: überspringt Tokens bis hinter das nächste `;` bzw. den Block des
+    // fehlerhaften Statements oder bis vor den Anfang eines Statements
+    fn synchronize(&mut self) {
+        while let Some(token) = &self.current_token {
+            match token.token {
+                Token::EOF | Token::RightBrace => return,
+                Token::Semicolon => {
+                    self.advance_token();
+                    return;
+                }
+                Token::LeftBrace => {
+                    self.skip_block();
+                    return;
+                }
+                Token::Var | Token::Fn | Token::If | Token::While | Token::Do | Token::Loop |
+                Token::For | Token::Switch | Token::Return | Token::Break | Token::Continue |
+                Token::Case | Token::Default => return,
+                _ => self.advance_token(),
+            }
+        }
     }
 
     // Statement-Parsing
     fn parse_statement(&mut self) -> Result<Statement, ParserError> {
-        match &self.current_token {
+        let start = self.start_position();
+        let kind = match &self.current_token {
             Some(TokenWithPosition { token: Token::Var, .. }) => {
                 self.parse_var_declaration()
             }
@@ -65,10 +236,22 @@ impl<'a> Parser<'a> {
                 self.parse_if_statement()
             }
             Some(TokenWithPosition { token: Token::While, .. }) => {
-                self.parse_while_statement()
+                self.parse_while_statement(None)
+            }
+            Some(TokenWithPosition { token: Token::Do, .. }) => {
+                self.parse_do_while_statement(None)
+            }
+            Some(TokenWithPosition { token: Token::Loop, .. }) => {
+                self.parse_loop_statement(None)
             }
             Some(TokenWithPosition { token: Token::For, .. }) => {
-                self.parse_for_statement()
+                self.parse_for_statement(None)
+            }
+            Some(TokenWithPosition { token: Token::Break, .. }) => {
+                self.parse_jump_statement()
+            }
+            Some(TokenWithPosition { token: Token::Continue, .. }) => {
+                self.parse_jump_statement()
             }
             Some(TokenWithPosition { token: Token::Switch, .. }) => {
                 self.parse_switch_statement()
@@ -79,41 +262,25 @@ impl<'a> Parser<'a> {
             Some(TokenWithPosition { token: Token::LeftBrace, .. }) => {
                 self.parse_block_statement()
             }
-            Some(TokenWithPosition { token: Token::Identifier(name), .. }) => {
-                let name = name.clone();
-                self.advance_token();
-                
-                if let Some(TokenWithPosition { token: Token::Assign, .. }) = &self.current_token {
-                    self.advance_token();
-                    let value = self.parse_expression(0)?;
-                    self.expect_token(Token::Semicolon)?;
-                    Ok(Statement::Expression(Expression::Binary {
-                        left: Box::new(Expression::Identifier(name)),
-                        operator: BinaryOperator::Assign,
-                        right: Box::new(value),
-                    }))
-                } else if let Some(TokenWithPosition { token: Token::LeftParen, .. }) = &self.current_token {
-                    let expr = self.parse_function_call(Expression::Identifier(name))?;
-                    self.expect_token(Token::Semicolon)?;
-                    Ok(Statement::Expression(expr))
-                } else {
-                    let expr = Expression::Identifier(name);
-                    self.expect_token(Token::Semicolon)?;
-                    Ok(Statement::Expression(expr))
-                }
+            Some(TokenWithPosition { token: Token::Identifier(_), .. })
+                if matches!(self.peek_token, Some(TokenWithPosition { token: Token::Colon, .. })) =>
+            {
+                self.parse_labeled_statement()
             }
+            Some(TokenWithPosition { token: Token::EOF, .. }) | None => Err(self.unexpected("statement")),
             Some(_) => {
                 let expr = self.parse_expression(0)?;
                 self.expect_token(Token::Semicolon)?;
-                Ok(Statement::Expression(expr))
+                Ok(StatementKind::Expression(expr))
             }
-            None => Err(ParserError::UnexpectedEOF),
-        }
+        }?;
+        Ok(Statement::new(kind, self.span_from(start)))
     }
 
     // Expression-Parsing mit Precedence Climbing
     fn parse_expression(&mut self, precedence: u8) -> Result<Expression, ParserError> {
-        let mut left = self.parse_primary()?;
+        let start = self.start_position();
+        let mut left = self.parse_unary()?;
 
         while let Some(token) = &self.current_token {
             if let Some(op) = self.get_binary_operator(&token.token) {
@@ -123,61 +290,217 @@ impl<'a> Parser<'a> {
                 
                 self.advance_token();
                 let right = self.parse_expression(op.precedence())?;
-                left = Expression::Binary {
-                    left: Box::new(left),
-                    operator: op,
-                    right: Box::new(right),
-                };
+                left = Expression::new(
+                    ExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: op,
+                        right: Box::new(right),
+                    },
+                    self.span_from(start),
+                );
             } else {
                 break;
             }
         }
 
+        // Zuweisungen binden am schwächsten und sind rechtsassoziativ
+        if precedence == 0 {
+            if let Some(TokenWithPosition { token: Token::Assign, .. }) = &self.current_token {
+                if !matches!(left.kind, ExpressionKind::Identifier(_) | ExpressionKind::Index { .. }) {
+                    return Err(ParserError::InvalidExpression {
+                        expected: "variable, index or field as assignment target".to_string(),
+                        found: Self::describe_expression(&left).to_string(),
+                        span: left.span,
+                    });
+                }
+                self.advance_token();
+                let value = self.parse_expression(0)?;
+                return Ok(Expression::new(
+                    ExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::Assign,
+                        right: Box::new(value),
+                    },
+                    self.span_from(start),
+                ));
+            }
+        }
+
         Ok(left)
     }
 
+    fn describe_expression(expression: &Expression) -> &'static str {
+        match expression.kind {
+            ExpressionKind::Number(_) => "number",
+            ExpressionKind::String(_) => "string",
+            ExpressionKind::Boolean(_) => "boolean",
+            ExpressionKind::Null => "null",
+            ExpressionKind::Identifier(_) => "identifier",
+            ExpressionKind::Binary { .. } => "binary expression",
+            ExpressionKind::Unary { .. } => "unary expression",
+            ExpressionKind::Call { .. } => "function call",
+            ExpressionKind::Array(_) => "array literal",
+            ExpressionKind::Map(_) => "map literal",
+            ExpressionKind::Index { .. } => "index expression",
+        }
+    }
+
+    // Präfix-Operatoren binden stärker als alle binären Operatoren, aber schwächer als Aufrufe/Indexzugriffe
+    fn parse_unary(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start_position();
+        let operator = match &self.current_token {
+            Some(TokenWithPosition { token: Token::Minus, .. }) => UnaryOperator::Minus,
+            Some(TokenWithPosition { token: Token::Not, .. }) => UnaryOperator::Not,
+            _ => return self.parse_primary(),
+        };
+        self.advance_token();
+        let operand = self.parse_unary()?;
+        Ok(Expression::new(
+            ExpressionKind::Unary {
+                operator,
+                operand: Box::new(operand),
+            },
+            self.span_from(start),
+        ))
+    }
+
     // Primäre Ausdrücke
     fn parse_primary(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start_position();
         let token = self.current_token.clone();
-        match token {
+        let kind = match token {
             Some(token) => {
                 match token.token {
                     Token::Number(n) => {
                         self.advance_token();
-                        Ok(Expression::number(n))
+                        Ok(ExpressionKind::Number(n))
                     },
                     Token::String(s) => {
                         self.advance_token();
-                        Ok(Expression::string(s))
+                        Ok(ExpressionKind::String(s))
                     },
                     Token::Identifier(name) => {
                         self.advance_token();
-                        if let Some(next) = &self.current_token {
-                            if next.token == Token::LeftParen {
-                                return self.parse_function_call(Expression::Identifier(name));
-                            }
-                        }
-                        Ok(Expression::identifier(name))
+                        Ok(ExpressionKind::Identifier(name))
                     },
                     Token::LeftParen => {
                         self.advance_token();
                         let expr = self.parse_expression(0)?;
                         self.expect_token(Token::RightParen)?;
-                        Ok(expr)
+                        Ok(expr.kind)
+                    },
+                    Token::LeftBracket => self.parse_array_literal(),
+                    Token::LeftBrace => self.parse_map_literal(),
+                    Token::Out => {
+                        // `out` ist ein Schlüsselwort, wird aber wie eine normale Funktion aufgerufen
+                        self.advance_token();
+                        Ok(ExpressionKind::Identifier("out".to_string()))
                     },
                     Token::True => {
                         self.advance_token();
-                        Ok(Expression::boolean(true))
+                        Ok(ExpressionKind::Boolean(true))
                     },
                     Token::False => {
                         self.advance_token();
-                        Ok(Expression::boolean(false))
+                        Ok(ExpressionKind::Boolean(false))
                     },
-                    _ => Err(ParserError::UnexpectedToken),
+                    Token::Null => {
+                        self.advance_token();
+                        Ok(ExpressionKind::Null)
+                    },
+                    _ => Err(self.unexpected("expression")),
                 }
             },
-            None => Err(ParserError::UnexpectedEOF),
+            None => Err(self.unexpected("expression")),
+        }?;
+        let expr = Expression::new(kind, self.span_from(start));
+        self.parse_postfix(expr)
+    }
+
+    // Aufrufe und Indexzugriffe hinter einem primären Ausdruck, z.B. `f(x)[0](y)`
+    fn parse_postfix(&mut self, mut expr: Expression) -> Result<Expression, ParserError> {
+        let start = expr.span.start;
+        loop {
+            let kind = match &self.current_token {
+                Some(TokenWithPosition { token: Token::LeftParen, .. }) => {
+                    self.parse_function_call(expr)?
+                }
+                Some(TokenWithPosition { token: Token::LeftBracket, .. }) => {
+                    self.advance_token(); // consume '['
+                    let index = self.parse_expression(0)?;
+                    self.expect_token(Token::RightBracket)?;
+                    ExpressionKind::Index {
+                        object: Box::new(expr),
+                        index: Box::new(index),
+                    }
+                }
+                Some(TokenWithPosition { token: Token::Dot, .. }) => {
+                    self.advance_token(); // consume '.'
+                    let field_span = self.current_span();
+                    let field = self.expect_identifier()?;
+                    ExpressionKind::Index {
+                        object: Box::new(expr),
+                        index: Box::new(Expression::new(ExpressionKind::String(field), field_span)),
+                    }
+                }
+                _ => return Ok(expr),
+            };
+            expr = Expression::new(kind, self.span_from(start));
+        }
+    }
+
+    fn parse_array_literal(&mut self) -> Result<ExpressionKind, ParserError> {
+        self.advance_token(); // consume '['
+        let mut elements = Vec::new();
+        
+        loop {
+            match &self.current_token {
+                Some(TokenWithPosition { token: Token::RightBracket, .. }) => break,
+                Some(TokenWithPosition { token: Token::EOF, .. }) | None => return Err(self.unexpected("']'")),
+                Some(_) => {
+                    elements.push(self.parse_expression(0)?);
+                    match &self.current_token {
+                        Some(TokenWithPosition { token: Token::Comma, .. }) => self.advance_token(),
+                        Some(TokenWithPosition { token: Token::RightBracket, .. }) => break,
+                        _ => return Err(self.unexpected("',' or ']'")),
+                    }
+                }
+            }
+        }
+        
+        self.expect_token(Token::RightBracket)?;
+        Ok(ExpressionKind::Array(elements))
+    }
+
+    fn parse_map_literal(&mut self) -> Result<ExpressionKind, ParserError> {
+        self.advance_token(); // consume '{'
+        let mut entries = Vec::new();
+        
+        loop {
+            // Schlüssel sind Bezeichner oder String-Literale
+            let key = match &self.current_token {
+                Some(TokenWithPosition { token: Token::RightBrace, .. }) => break,
+                Some(TokenWithPosition { token: Token::Identifier(key), .. }) => key.clone(),
+                Some(TokenWithPosition { token: Token::String(key), .. }) => key.clone(),
+                Some(TokenWithPosition { token: Token::EOF, .. }) | None => return Err(self.unexpected("'}'")),
+                Some(_) => return Err(ParserError::ExpectedIdentifier {
+                    found: self.found(),
+                    span: self.current_span(),
+                }),
+            };
+            self.advance_token();
+            self.expect_token(Token::Colon)?;
+            entries.push((key, self.parse_expression(0)?));
+            
+            match &self.current_token {
+                Some(TokenWithPosition { token: Token::Comma, .. }) => self.advance_token(),
+                Some(TokenWithPosition { token: Token::RightBrace, .. }) => break,
+                _ => return Err(self.unexpected("',' or '}'")),
+            }
         }
+        
+        self.expect_token(Token::RightBrace)?;
+        Ok(ExpressionKind::Map(entries))
     }
 
     // Hilfsmethoden
@@ -200,48 +523,26 @@ impl<'a> Parser<'a> {
     }
 
     // Implementierung der Statement-Parser
-    fn parse_var_declaration(&mut self) -> Result<Statement, ParserError> {
+    fn parse_var_declaration(&mut self) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'var'
         
-        if let Some(TokenWithPosition { token: Token::Identifier(name), .. }) = &self.current_token {
-            let name = name.clone();
+        let name = self.expect_identifier()?;
+
+        let initializer = if let Some(TokenWithPosition { token: Token::Assign, .. }) = &self.current_token {
             self.advance_token();
-            
-            let initializer = if let Some(token) = &self.current_token {
-                if token.token == Token::Assign {
-                    self.advance_token();
-                    Some(self.parse_expression(0)?)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-            
-            if let Some(token) = &self.current_token {
-                if token.token == Token::Semicolon {
-                    self.advance_token();
-                    Ok(Statement::VarDeclaration { name, initializer })
-                } else {
-                    Err(ParserError::UnexpectedToken)
-                }
-            } else {
-                Err(ParserError::UnexpectedEOF)
-            }
+            Some(self.parse_expression(0)?)
         } else {
-            Err(ParserError::ExpectedIdentifier)
-        }
+            None
+        };
+            
+        self.expect_token(Token::Semicolon)?;
+        Ok(StatementKind::VarDeclaration { name, initializer })
     }
 
-    fn parse_function_declaration(&mut self) -> Result<Statement, ParserError> {
+    fn parse_function_declaration(&mut self) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'fn'
         
-        let name = if let Some(TokenWithPosition { token: Token::Identifier(name), .. }) = &self.current_token {
-            name.clone()
-        } else {
-            return Err(ParserError::ExpectedIdentifier);
-        };
-        self.advance_token();
+        let name = self.expect_identifier()?;
         
         self.expect_token(Token::LeftParen)?;
         let mut params = Vec::new();
@@ -249,151 +550,196 @@ impl<'a> Parser<'a> {
         if let Some(token) = &self.current_token {
             if token.token != Token::RightParen {
                 loop {
-                    if let Some(TokenWithPosition { token: Token::Identifier(param), .. }) = &self.current_token {
-                        params.push(param.clone());
-                        self.advance_token();
+                    params.push(self.expect_identifier()?);
                         
-                        if let Some(token) = &self.current_token {
-                            match token.token {
-                                Token::RightParen => break,
-                                Token::Comma => {
-                                    self.advance_token();
-                                    continue;
-                                }
-                                _ => return Err(ParserError::UnexpectedToken),
-                            }
-                        } else {
-                            return Err(ParserError::UnexpectedEOF);
+                    match &self.current_token {
+                        Some(TokenWithPosition { token: Token::RightParen, .. }) => break,
+                        Some(TokenWithPosition { token: Token::Comma, .. }) => {
+                            self.advance_token();
+                            continue;
                         }
-                    } else {
-                        return Err(ParserError::ExpectedIdentifier);
+                        _ => return Err(self.unexpected("',' or ')'")),
                     }
                 }
             }
         }
         
         self.expect_token(Token::RightParen)?;
-        let body = self.parse_block_statement()?;
         
-        if let Statement::Block(statements) = body {
-            Ok(Statement::FunctionDeclaration {
+        if !matches!(self.current_token, Some(TokenWithPosition { token: Token::LeftBrace, .. })) {
+            return Err(ParserError::ExpectedBlock {
+                found: self.found(),
+                span: self.current_span(),
+            });
+        }
+
+        // Schleifen außerhalb der Funktion sind im Funktionskörper keine Sprungziele
+        let outer_targets = std::mem::take(&mut self.jump_targets);
+        let body = self.parse_block_statement();
+        self.jump_targets = outer_targets;
+        
+        match body? {
+            StatementKind::Block(statements) => Ok(StatementKind::FunctionDeclaration {
                 name,
                 params,
                 body: statements,
-            })
-        } else {
-            Err(ParserError::ExpectedBlock)
+            }),
+            _ => unreachable!("parse_block_statement returns a block"),
         }
     }
 
-    fn parse_block_statement(&mut self) -> Result<Statement, ParserError> {
+    fn parse_block_statement(&mut self) -> Result<StatementKind, ParserError> {
         self.expect_token(Token::LeftBrace)?;
         let mut statements = Vec::new();
         
-        while let Some(TokenWithPosition { token, .. }) = &self.current_token {
-            if *token == Token::RightBrace {
+        while !self.is_at_end() {
+            if let Some(TokenWithPosition { token: Token::RightBrace, .. }) = &self.current_token {
                 break;
             }
-            statements.push(self.parse_statement()?);
+            if let Some(stmt) = self.parse_statement_or_recover() {
+                statements.push(stmt);
+            }
         }
         
         self.expect_token(Token::RightBrace)?;
-        Ok(Statement::Block(statements))
+        Ok(StatementKind::Block(statements))
     }
 
-    fn parse_if_statement(&mut self) -> Result<Statement, ParserError> {
+    // Zweig eines `if`: ein Block oder ein einzelnes Statement, das in einen Block gepackt wird
+    fn parse_branch(&mut self) -> Result<Box<Statement>, ParserError> {
+        let stmt = self.parse_statement()?;
+        if let StatementKind::Block(_) = stmt.kind {
+            Ok(Box::new(stmt))
+        } else {
+            let span = stmt.span;
+            Ok(Box::new(Statement::new(StatementKind::Block(vec![stmt]), span)))
+        }
+    }
+
+    fn parse_if_statement(&mut self) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'if'
         
         self.expect_token(Token::LeftParen)?;
         let condition = self.parse_expression(0)?;
         self.expect_token(Token::RightParen)?;
         
-        // Für den Then-Branch erwarten wir einen Block oder ein einzelnes Statement
-        let then_branch = if let Some(TokenWithPosition { token: Token::LeftBrace, .. }) = &self.current_token {
-            Box::new(self.parse_block_statement()?)
-        } else {
-            let stmt = self.parse_statement()?;
-            Box::new(Statement::Block(vec![stmt]))
-        };
+        let then_branch = self.parse_branch()?;
         
-        // Für den Else-Branch das gleiche
         let else_branch = if let Some(TokenWithPosition { token: Token::Else, .. }) = &self.current_token {
             self.advance_token(); // consume 'else'
-            if let Some(TokenWithPosition { token: Token::LeftBrace, .. }) = &self.current_token {
-                Some(Box::new(self.parse_block_statement()?))
-            } else {
-                let stmt = self.parse_statement()?;
-                Some(Box::new(Statement::Block(vec![stmt])))
-            }
+            Some(self.parse_branch()?)
         } else {
             None
         };
         
-        Ok(Statement::If {
+        Ok(StatementKind::If {
             condition,
             then_branch,
             else_branch,
         })
     }
 
-    fn parse_while_statement(&mut self) -> Result<Statement, ParserError> {
+    fn parse_labeled_statement(&mut self) -> Result<StatementKind, ParserError> {
+        let label = self.expect_identifier()?;
+        self.expect_token(Token::Colon)?;
+        
+        // Labels sind nur vor Schleifen erlaubt
+        match &self.current_token {
+            Some(TokenWithPosition { token: Token::While, .. }) => self.parse_while_statement(Some(label)),
+            Some(TokenWithPosition { token: Token::Do, .. }) => self.parse_do_while_statement(Some(label)),
+            Some(TokenWithPosition { token: Token::Loop, .. }) => self.parse_loop_statement(Some(label)),
+            Some(TokenWithPosition { token: Token::For, .. }) => self.parse_for_statement(Some(label)),
+            Some(TokenWithPosition { token: Token::EOF, .. }) | None => Err(self.unexpected("loop after label")),
+            Some(_) => Err(ParserError::InvalidStatement {
+                expected: "loop after label".to_string(),
+                found: self.found(),
+                span: self.current_span(),
+            }),
+        }
+    }
+
+    // Parst einen Schleifenkörper, in dem break/continue auf diese Schleife zeigen dürfen
+    fn parse_loop_body(&mut self, label: &Option<String>) -> Result<Box<Statement>, ParserError> {
+        self.jump_targets.push(JumpTarget::Loop(label.clone()));
+        let body = self.parse_statement();
+        self.jump_targets.pop();
+        Ok(Box::new(body?))
+    }
+
+    fn parse_while_statement(&mut self, label: Option<String>) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'while'
         
         self.expect_token(Token::LeftParen)?;
         let condition = self.parse_expression(0)?;
         self.expect_token(Token::RightParen)?;
         
-        let body = Box::new(self.parse_statement()?);
+        let body = self.parse_loop_body(&label)?;
         
-        Ok(Statement::While {
+        Ok(StatementKind::While {
+            label,
             condition,
             body,
         })
     }
 
-    fn parse_for_statement(&mut self) -> Result<Statement, ParserError> {
+    fn parse_do_while_statement(&mut self, label: Option<String>) -> Result<StatementKind, ParserError> {
+        self.advance_token(); // consume 'do'
+        
+        let body = self.parse_loop_body(&label)?;
+        
+        self.expect_token(Token::While)?;
+        self.expect_token(Token::LeftParen)?;
+        let condition = self.parse_expression(0)?;
+        self.expect_token(Token::RightParen)?;
+        self.expect_token(Token::Semicolon)?;
+        
+        Ok(StatementKind::DoWhile {
+            label,
+            body,
+            condition,
+        })
+    }
+
+    fn parse_loop_statement(&mut self, label: Option<String>) -> Result<StatementKind, ParserError> {
+        self.advance_token(); // consume 'loop'
+        
+        let body = self.parse_loop_body(&label)?;
+        
+        Ok(StatementKind::Loop { label, body })
+    }
+
+    fn parse_for_statement(&mut self, label: Option<String>) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'for'
         
         self.expect_token(Token::LeftParen)?;
         
         // Initializer
-        let initializer = if let Some(token) = &self.current_token {
-            if token.token == Token::Semicolon {
-                None
-            } else {
-                Some(Box::new(self.parse_statement()?))
-            }
+        let initializer = if let Some(TokenWithPosition { token: Token::Semicolon, .. }) = &self.current_token {
+            None
         } else {
-            return Err(ParserError::UnexpectedEOF);
+            Some(Box::new(self.parse_statement()?))
         };
         
         // Condition
-        let condition = if let Some(token) = &self.current_token {
-            if token.token == Token::Semicolon {
-                None
-            } else {
-                Some(self.parse_expression(0)?)
-            }
+        let condition = if let Some(TokenWithPosition { token: Token::Semicolon, .. }) = &self.current_token {
+            None
         } else {
-            return Err(ParserError::UnexpectedEOF);
+            Some(self.parse_expression(0)?)
         };
         self.expect_token(Token::Semicolon)?;
         
         // Increment
-        let increment = if let Some(token) = &self.current_token {
-            if token.token == Token::RightParen {
-                None
-            } else {
-                Some(self.parse_expression(0)?)
-            }
+        let increment = if let Some(TokenWithPosition { token: Token::RightParen, .. }) = &self.current_token {
+            None
         } else {
-            return Err(ParserError::UnexpectedEOF);
+            Some(self.parse_expression(0)?)
         };
         self.expect_token(Token::RightParen)?;
         
-        let body = Box::new(self.parse_statement()?);
+        let body = self.parse_loop_body(&label)?;
         
-        Ok(Statement::For {
+        Ok(StatementKind::For {
+            label,
             initializer,
             condition,
             increment,
@@ -401,7 +747,48 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_switch_statement(&mut self) -> Result<Statement, ParserError> {
+    fn parse_jump_statement(&mut self) -> Result<StatementKind, ParserError> {
+        let start = self.start_position();
+        let is_break = matches!(self.current_token, Some(TokenWithPosition { token: Token::Break, .. }));
+        let keyword = if is_break { "break" } else { "continue" };
+        self.advance_token(); // consume 'break' / 'continue'
+        
+        let label_span = self.current_span();
+        let label = if let Some(TokenWithPosition { token: Token::Identifier(name), .. }) = &self.current_token {
+            let name = name.clone();
+            self.advance_token();
+            Some(name)
+        } else {
+            None
+        };
+        self.expect_token(Token::Semicolon)?;
+        
+        // Das Sprungziel muss eine umschließende Schleife sein (ein unbenanntes break darf auch einen Switch verlassen)
+        let has_target = self.jump_targets.iter().any(|target| match (target, &label) {
+            (JumpTarget::Loop(loop_label), Some(label)) => loop_label.as_ref() == Some(label),
+            (JumpTarget::Loop(_), None) => true,
+            (JumpTarget::Switch, None) => is_break,
+            (JumpTarget::Switch, Some(_)) => false,
+        });
+        // Das Statement ist syntaktisch vollständig, daher wird nur der Fehler vermerkt
+        if !has_target {
+            self.diagnostics.push(match &label {
+                Some(label) => ParserError::UndefinedLabel { label: label.clone(), span: label_span },
+                None => ParserError::JumpOutsideLoop {
+                    keyword: keyword.to_string(),
+                    span: self.span_from(start),
+                },
+            });
+        }
+        
+        Ok(if is_break {
+            StatementKind::Break(label)
+        } else {
+            StatementKind::Continue(label)
+        })
+    }
+
+    fn parse_switch_statement(&mut self) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'switch'
         
         self.expect_token(Token::LeftParen)?;
@@ -410,6 +797,22 @@ impl<'a> Parser<'a> {
         
         self.expect_token(Token::LeftBrace)?;
         
+        self.jump_targets.push(JumpTarget::Switch);
+        let cases = self.parse_switch_cases();
+        self.jump_targets.pop();
+        let (cases, default) = cases?;
+        
+        self.expect_token(Token::RightBrace)?;
+        
+        Ok(StatementKind::Switch {
+            condition,
+            cases,
+            default,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn parse_switch_cases(&mut self) -> Result<(Vec<(Expression, Vec<Statement>)>, Option<Vec<Statement>>), ParserError> {
         let mut cases = Vec::new();
         let mut default = None;
         
@@ -420,109 +823,75 @@ impl<'a> Parser<'a> {
                     self.advance_token();
                     let case_value = self.parse_expression(0)?;
                     self.expect_token(Token::Colon)?;
-                    
-                    let mut statements = Vec::new();
-                    while let Some(token) = &self.current_token {
-                        match token.token {
-                            Token::Case | Token::Default | Token::RightBrace => break,
-                            _ => statements.push(self.parse_statement()?),
-                        }
-                    }
-                    cases.push((case_value, statements));
+                    cases.push((case_value, self.parse_case_body()?));
                 },
                 Token::Default => {
                     self.advance_token();
                     self.expect_token(Token::Colon)?;
-                    
-                    let mut statements = Vec::new();
-                    while let Some(token) = &self.current_token {
-                        match token.token {
-                            Token::Case | Token::Default | Token::RightBrace => break,
-                            _ => statements.push(self.parse_statement()?),
-                        }
-                    }
-                    default = Some(statements);
+                    default = Some(self.parse_case_body()?);
                 },
-                _ => return Err(ParserError::UnexpectedToken),
+                _ => return Err(self.unexpected("'case', 'default' or '}'")),
             }
         }
         
-        self.expect_token(Token::RightBrace)?;
-        
-        Ok(Statement::Switch {
-            condition,
-            cases,
-            default,
-        })
+        Ok((cases, default))
+    }
+
+    fn parse_case_body(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let mut statements = Vec::new();
+        while let Some(token) = &self.current_token {
+            match token.token {
+                Token::Case | Token::Default | Token::RightBrace | Token::EOF => break,
+                _ => statements.extend(self.parse_statement_or_recover()),
+            }
+        }
+        Ok(statements)
     }
 
-    fn parse_return_statement(&mut self) -> Result<Statement, ParserError> {
+    fn parse_return_statement(&mut self) -> Result<StatementKind, ParserError> {
         self.advance_token(); // consume 'return'
         
-        let value = if let Some(token) = &self.current_token {
-            if token.token == Token::Semicolon {
-                None
-            } else {
-                Some(self.parse_expression(0)?)
-            }
+        let value = if let Some(TokenWithPosition { token: Token::Semicolon, .. }) = &self.current_token {
+            None
         } else {
-            return Err(ParserError::UnexpectedEOF);
+            Some(self.parse_expression(0)?)
         };
         
         self.expect_token(Token::Semicolon)?;
         
-        Ok(Statement::Return(value))
+        Ok(StatementKind::Return(value))
     }
 
-    fn parse_function_call(&mut self, callee: Expression) -> Result<Expression, ParserError> {
+    fn parse_function_call(&mut self, callee: Expression) -> Result<ExpressionKind, ParserError> {
         self.advance_token(); // consume '('
         let mut arguments = Vec::new();
         
         if let Some(token) = &self.current_token {
             if token.token != Token::RightParen {
                 loop {
-                    // Check for unary minus
-                    let mut is_negative = false;
-                    if let Some(TokenWithPosition { token: Token::Minus, .. }) = &self.current_token {
-                        is_negative = true;
-                        self.advance_token();
-                    }
-                    
-                    let mut arg = self.parse_expression(0)?;
-                    
-                    // Apply the negative sign if needed
-                    if is_negative {
-                        if let Expression::Number(n) = arg {
-                            arg = Expression::Number(-n);
-                        }
-                    }
-                    
-                    arguments.push(arg);
+                    arguments.push(self.parse_expression(0)?);
                     
-                    if let Some(token) = &self.current_token {
-                        match token.token {
-                            Token::RightParen => break,
-                            Token::Comma => {
-                                self.advance_token();
-                                continue;
-                            }
-                            _ => return Err(ParserError::UnexpectedToken),
+                    match &self.current_token {
+                        Some(TokenWithPosition { token: Token::RightParen, .. }) => break,
+                        Some(TokenWithPosition { token: Token::Comma, .. }) => {
+                            self.advance_token();
+                            continue;
                         }
-                    } else {
-                        return Err(ParserError::UnexpectedEOF);
+                        _ => return Err(self.unexpected("',' or ')'")),
                     }
                 }
             }
         }
         
         self.expect_token(Token::RightParen)?;
-        Ok(Expression::Call { callee: Box::new(callee), arguments })
+        Ok(ExpressionKind::Call { callee: Box::new(callee), arguments })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::error::LexerError;
 
     #[test]
     fn test_parse_variable_declaration() {
@@ -531,9 +900,9 @@ mod tests {
         let result = parser.parse_program().unwrap();
         
         assert_eq!(result.len(), 1);
-        if let Statement::VarDeclaration { name, initializer } = &result[0] {
+        if let StatementKind::VarDeclaration { name, initializer } = &result[0].kind {
             assert_eq!(name, "x");
-            if let Some(Expression::Number(value)) = initializer {
+            if let Some(ExpressionKind::Number(value)) = initializer.as_ref().map(|expr| &expr.kind) {
                 assert_eq!(*value, 42.0);
             } else {
                 panic!("Expected number initializer");
@@ -550,7 +919,7 @@ mod tests {
         let result = parser.parse_program().unwrap();
         
         assert_eq!(result.len(), 1);
-        if let Statement::FunctionDeclaration { name, params, body } = &result[0] {
+        if let StatementKind::FunctionDeclaration { name, params, body } = &result[0].kind {
             assert_eq!(name, "add");
             assert_eq!(params, &vec!["x".to_string(), "y".to_string()]);
             assert_eq!(body.len(), 1);
@@ -566,8 +935,8 @@ mod tests {
         let result = parser.parse_program().unwrap();
         
         assert_eq!(result.len(), 1);
-        if let Statement::If { condition, then_branch: _, else_branch } = &result[0] {
-            if let Expression::Binary { operator, .. } = condition {
+        if let StatementKind::If { condition, then_branch: _, else_branch } = &result[0].kind {
+            if let ExpressionKind::Binary { operator, .. } = &condition.kind {
                 assert_eq!(*operator, BinaryOperator::Greater);
             } else {
                 panic!("Expected binary expression");
@@ -585,14 +954,14 @@ mod tests {
         let result = parser.parse_program().unwrap();
         
         assert_eq!(result.len(), 1);
-        if let Statement::Expression(Expression::Binary { left, operator, right }) = &result[0] {
+        if let StatementKind::Expression(Expression { kind: ExpressionKind::Binary { left, operator, right }, .. }) = &result[0].kind {
             assert_eq!(*operator, BinaryOperator::Add);
-            if let Expression::Number(left_val) = &**left {
+            if let ExpressionKind::Number(left_val) = &left.kind {
                 assert_eq!(*left_val, 2.0);
             } else {
                 panic!("Expected number on left side");
             }
-            if let Expression::Binary { operator, .. } = &**right {
+            if let ExpressionKind::Binary { operator, .. } = &right.kind {
                 assert_eq!(*operator, BinaryOperator::Multiply);
             } else {
                 panic!("Expected multiplication on right side");
@@ -602,6 +971,67 @@ mod tests {
         }
     }
 
+    fn parse_single_expression(input: &str) -> Expression {
+        let mut parser = Parser::new(input);
+        match parser.parse_program().unwrap().as_slice() {
+            [Statement { kind: StatementKind::Expression(expr), .. }] => expr.clone(),
+            other => panic!("Expected a single expression statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_unary_operators() {
+        let x = || Expression::identifier("x".to_string());
+
+        assert_eq!(parse_single_expression("-x;"), Expression::unary(UnaryOperator::Minus, x()));
+        assert_eq!(
+            parse_single_expression("!!x;"),
+            Expression::unary(UnaryOperator::Not, Expression::unary(UnaryOperator::Not, x()))
+        );
+        assert_eq!(
+            parse_single_expression("- -3;"),
+            Expression::unary(UnaryOperator::Minus, Expression::unary(UnaryOperator::Minus, Expression::number(3.0)))
+        );
+        assert_eq!(
+            parse_single_expression("-(x + 1);"),
+            Expression::unary(
+                UnaryOperator::Minus,
+                Expression::binary(x(), BinaryOperator::Add, Expression::number(1.0))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_unary_precedence() {
+        let x = || Expression::identifier("x".to_string());
+
+        // Unär bindet stärker als binäre Operatoren ...
+        assert_eq!(
+            parse_single_expression("-2 * x;"),
+            Expression::binary(
+                Expression::unary(UnaryOperator::Minus, Expression::number(2.0)),
+                BinaryOperator::Multiply,
+                x()
+            )
+        );
+        assert_eq!(
+            parse_single_expression("!x == false;"),
+            Expression::binary(
+                Expression::unary(UnaryOperator::Not, x()),
+                BinaryOperator::Equal,
+                Expression::boolean(false)
+            )
+        );
+        // ... aber schwächer als Aufrufe und Feldzugriffe
+        assert_eq!(
+            parse_single_expression("-x.cpu;"),
+            Expression::unary(
+                UnaryOperator::Minus,
+                Expression::index(x(), Expression::string("cpu".to_string()))
+            )
+        );
+    }
+
     #[test]
     fn test_parse_function_call() {
         let input = "print(\"Hello\", 42);";
@@ -609,8 +1039,8 @@ mod tests {
         let result = parser.parse_program().unwrap();
         
         assert_eq!(result.len(), 1);
-        if let Statement::Expression(Expression::Call { callee, arguments }) = &result[0] {
-            if let Expression::Identifier(name) = &**callee {
+        if let StatementKind::Expression(Expression { kind: ExpressionKind::Call { callee, arguments }, .. }) = &result[0].kind {
+            if let ExpressionKind::Identifier(name) = &callee.kind {
                 assert_eq!(name, "print");
             } else {
                 panic!("Expected function identifier");
@@ -628,13 +1058,110 @@ mod tests {
         let result = parser.parse_program().unwrap();
         
         assert_eq!(result.len(), 1);
-        if let Statement::Block(statements) = &result[0] {
+        if let StatementKind::Block(statements) = &result[0].kind {
             assert_eq!(statements.len(), 2);
         } else {
             panic!("Expected block statement");
         }
     }
 
+    #[test]
+    fn test_parse_labeled_loop() {
+        let input = "outer: while (true) { loop { break outer; } }";
+        let mut parser = Parser::new(input);
+        let result = parser.parse_program().unwrap();
+        
+        assert_eq!(result.len(), 1);
+        if let StatementKind::While { label, .. } = &result[0].kind {
+            assert_eq!(label.as_deref(), Some("outer"));
+        } else {
+            panic!("Expected while statement");
+        }
+    }
+
+    #[test]
+    fn test_parse_do_while() {
+        let input = "do { x = x + 1; } while (x < 3);";
+        let mut parser = Parser::new(input);
+        let result = parser.parse_program().unwrap();
+        
+        assert_eq!(result.len(), 1);
+        assert!(matches!(&result[0].kind, StatementKind::DoWhile { label: None, .. }));
+    }
+
+    #[test]
+    fn test_parse_error_jump_outside_loop() {
+        let mut parser = Parser::new("break;");
+        assert!(matches!(parser.parse_program(), Err(ParserError::JumpOutsideLoop { .. })));
+
+        let mut parser = Parser::new("while (true) { fn f() { continue; } }");
+        assert!(matches!(parser.parse_program(), Err(ParserError::JumpOutsideLoop { .. })));
+
+        let mut parser = Parser::new("switch (1) { case 1: continue; }");
+        assert!(matches!(parser.parse_program(), Err(ParserError::JumpOutsideLoop { .. })));
+    }
+
+    #[test]
+    fn test_parse_error_undefined_label() {
+        let input = "inner: loop { break outer; }";
+        let mut parser = Parser::new(input);
+        assert!(matches!(parser.parse_program(), Err(ParserError::UndefinedLabel { label, .. }) if label == "outer"));
+    }
+
+    #[test]
+    fn test_parse_array_literal_and_index() {
+        let input = "var a = [1, [2, 3], \"x\"]; a[1][0] = a[0];";
+        let mut parser = Parser::new(input);
+        let result = parser.parse_program().unwrap();
+        
+        assert_eq!(result.len(), 2);
+        if let StatementKind::VarDeclaration { initializer: Some(Expression { kind: ExpressionKind::Array(elements), .. }), .. } = &result[0].kind {
+            assert_eq!(elements.len(), 3);
+            assert!(matches!(&elements[1].kind, ExpressionKind::Array(inner) if inner.len() == 2));
+        } else {
+            panic!("Expected array initializer");
+        }
+        if let StatementKind::Expression(Expression { kind: ExpressionKind::Binary { left, operator, .. }, .. }) = &result[1].kind {
+            assert_eq!(*operator, BinaryOperator::Assign);
+            if let ExpressionKind::Index { object, .. } = &left.kind {
+                assert!(matches!(&object.kind, ExpressionKind::Index { .. }));
+            } else {
+                panic!("Expected index target");
+            }
+        } else {
+            panic!("Expected assignment");
+        }
+    }
+
+    #[test]
+    fn test_parse_map_literal_and_field_access() {
+        let input = "var p = { \"pid\": 1, name: \"aion.bin\", }; p.name = p[\"name\"];";
+        let mut parser = Parser::new(input);
+        let result = parser.parse_program().unwrap();
+        
+        assert_eq!(result.len(), 2);
+        if let StatementKind::VarDeclaration { initializer: Some(Expression { kind: ExpressionKind::Map(entries), .. }), .. } = &result[0].kind {
+            let keys: Vec<&str> = entries.iter().map(|(key, _)| key.as_str()).collect();
+            assert_eq!(keys, vec!["pid", "name"]);
+        } else {
+            panic!("Expected map initializer");
+        }
+        if let StatementKind::Expression(Expression { kind: ExpressionKind::Binary { left, .. }, .. }) = &result[1].kind {
+            assert_eq!(**left, Expression::index(
+                Expression::identifier("p".to_string()),
+                Expression::string("name".to_string()),
+            ));
+        } else {
+            panic!("Expected assignment");
+        }
+    }
+
+    #[test]
+    fn test_parse_error_invalid_assignment_target() {
+        let mut parser = Parser::new("1 + 2 = 3;");
+        assert!(matches!(parser.parse_program(), Err(ParserError::InvalidExpression { .. })));
+    }
+
     #[test]
     fn test_parse_error_unexpected_token() {
         let input = "var 42;";
@@ -648,7 +1175,7 @@ mod tests {
         let input = "var x =";
         let mut parser = Parser::new(input);
         let result = parser.parse_program();
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ParserError::UnexpectedEOF { .. })));
     }
 
     #[test]
@@ -656,6 +1183,141 @@ mod tests {
         let input = "fn test() return x;";
         let mut parser = Parser::new(input);
         let result = parser.parse_program();
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ParserError::ExpectedBlock { .. })));
     }
-} 
\ No newline at end of file
+    fn span(line: usize, start: usize, end: usize) -> Span {
+        Span {
+            start: Position { line, column: start },
+            end: Position { line, column: end },
+        }
+    }
+
+    #[test]
+    fn test_parse_node_spans() {
+        let input = "var x = 1;\nx = -x + f(2)[0];";
+        let mut parser = Parser::new(input);
+        let result = parser.parse_program().unwrap();
+
+        assert_eq!(result[0].span, span(1, 1, 11));
+        assert_eq!(result[1].span, span(2, 1, 18));
+        if let StatementKind::Expression(Expression { kind: ExpressionKind::Binary { left, right, .. }, span: assign_span }) = &result[1].kind {
+            assert_eq!(*assign_span, span(2, 1, 17));
+            assert_eq!(left.span, span(2, 1, 2));
+            assert_eq!(right.span, span(2, 5, 17));
+            if let ExpressionKind::Binary { left, right, .. } = &right.kind {
+                assert_eq!(left.span, span(2, 5, 7));
+                assert_eq!(right.span, span(2, 10, 17));
+            } else {
+                panic!("Expected addition");
+            }
+        } else {
+            panic!("Expected assignment");
+        }
+    }
+
+    #[test]
+    fn test_parse_error_expected_and_found() {
+        let mut parser = Parser::new("var x = (1 + 2;");
+        match parser.parse_program() {
+            Err(ParserError::UnexpectedToken { expected, found, span: error_span }) => {
+                assert_eq!(expected, "')'");
+                assert_eq!(found, "';'");
+                assert_eq!(error_span, span(1, 15, 16));
+            },
+            other => panic!("Expected unexpected token error, got {:?}", other),
+        }
+
+        let mut parser = Parser::new("var 42;");
+        let error = parser.parse_program().unwrap_err();
+        assert!(matches!(&error, ParserError::ExpectedIdentifier { found, .. } if found == "number 42"));
+        assert_eq!(error.to_string(), "Expected identifier, found number 42 at line 1, column 5");
+
+        let mut parser = Parser::new("fn test() {\n  return 1;\n");
+        assert!(matches!(
+            parser.parse_program(),
+            Err(ParserError::UnexpectedEOF { expected, .. }) if expected == "'}'"
+        ));
+    }
+
+    #[test]
+    fn test_parse_error_spans() {
+        let mut parser = Parser::new("while (true) {}\nbreak;");
+        assert_eq!(parser.parse_program().unwrap_err().span(), span(2, 1, 7));
+
+        let mut parser = Parser::new("loop { continue outer; }");
+        assert_eq!(parser.parse_program().unwrap_err().span(), span(1, 17, 22));
+
+        let mut parser = Parser::new("x + 1 = 2;");
+        assert_eq!(parser.parse_program().unwrap_err().span(), span(1, 1, 6));
+    }
+    #[test]
+    fn test_recovery_reports_all_errors() {
+        let input = "var = 1;\nvar y = 2;\nout(y +);\nvar z = 3;";
+        let mut parser = Parser::new(input);
+        let output = parser.parse_with_recovery();
+
+        assert_eq!(output.diagnostics.len(), 2);
+        assert!(matches!(&output.diagnostics[0], ParserError::ExpectedIdentifier { span, .. } if span.start.line == 1));
+        assert!(matches!(&output.diagnostics[1], ParserError::UnexpectedToken { found, span, .. } if found == "')'" && span.start.line == 3));
+
+        let names: Vec<&str> = output.statements.iter().filter_map(|stmt| match &stmt.kind {
+            StatementKind::VarDeclaration { name, .. } => Some(name.as_str()),
+            _ => None,
+        }).collect();
+        assert_eq!(names, vec!["y", "z"]);
+    }
+
+    #[test]
+    fn test_recovery_inside_blocks() {
+        let input = "fn f() {\n  var a = ;\n  return 1;\n}\nf();";
+        let mut parser = Parser::new(input);
+        let output = parser.parse_with_recovery();
+
+        assert_eq!(output.diagnostics.len(), 1);
+        assert_eq!(output.statements.len(), 2);
+        if let StatementKind::FunctionDeclaration { body, .. } = &output.statements[0].kind {
+            assert!(matches!(body.as_slice(), [Statement { kind: StatementKind::Return(_), .. }]));
+        } else {
+            panic!("Expected function declaration");
+        }
+    }
+
+    #[test]
+    fn test_recovery_skips_broken_statement_block() {
+        let input = "if (x +) { out(1); }\nvar y = 2;";
+        let mut parser = Parser::new(input);
+        let output = parser.parse_with_recovery();
+
+        assert_eq!(output.diagnostics.len(), 1);
+        assert!(matches!(output.statements.as_slice(), [Statement { kind: StatementKind::VarDeclaration { .. }, .. }]));
+    }
+
+    #[test]
+    fn test_recovery_makes_progress_on_stray_tokens() {
+        let mut parser = Parser::new("} ) var x = 1;");
+        let output = parser.parse_with_recovery();
+
+        assert_eq!(output.diagnostics.len(), 2);
+        assert_eq!(output.statements.len(), 1);
+    }
+
+    #[test]
+    fn test_lexer_errors_are_reported() {
+        let mut parser = Parser::new("var x = @;\nvar s = \"open");
+        let output = parser.parse_with_recovery();
+
+        assert!(matches!(
+            output.diagnostics.as_slice(),
+            [
+                ParserError::Lexer(LexerError::InvalidCharacter('@', 1, 9)),
+                ParserError::UnexpectedToken { .. },
+                ParserError::Lexer(LexerError::UnterminatedString(2, 9)),
+                ParserError::UnexpectedEOF { .. },
+            ]
+        ), "{:?}", output.diagnostics);
+
+        // parse_program liefert den ersten Fehler statt eines irreführenden Dateiendes
+        let mut parser = Parser::new("var x = @;");
+        assert!(matches!(parser.parse_program(), Err(ParserError::Lexer(LexerError::InvalidCharacter('@', _, _)))));
+    }
+}
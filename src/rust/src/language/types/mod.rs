@@ -13,8 +13,13 @@ pub enum Token {
     Default,
     Out,
     Return,
+    Break,
+    Continue,
+    Do,
+    Loop,
     True,   // Neues Token
     False,  // Neues Token
+    Null,
     
     // Literale
     Identifier(String),
@@ -42,24 +47,115 @@ pub enum Token {
     RightParen,   // )
     LeftBrace,    // {
     RightBrace,   // }
+    LeftBracket,  // [
+    RightBracket, // ]
     Comma,        // ,
     Semicolon,    // ;
     Colon,        // :
+    Dot,          // .
     
     // Spezielle Token
     EOF,
 }
 
 // Position im Quellcode für bessere Fehlermeldungen
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Position {
     pub line: usize,
     pub column: usize,
 }
 
+// Bereich im Quellcode; `end` zeigt direkt hinter das letzte Zeichen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
 // Token mit Position
 #[derive(Debug, Clone)]
 pub struct TokenWithPosition {
     pub token: Token,
     pub position: Position,
+    pub end: Position,
+}
+
+impl TokenWithPosition {
+    pub fn span(&self) -> Span {
+        Span {
+            start: self.position,
+            end: self.end,
+        }
+    }
+}
+
+impl Span {
+    // Kleinster Bereich, der beide Bereiche umfasst
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+// Beschreibung eines Tokens für Fehlermeldungen, z.B. "')'" oder "identifier 'x'"
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let symbol = match self {
+            Token::Identifier(name) => return write!(f, "identifier '{}'", name),
+            Token::Number(n) => return write!(f, "number {}", n),
+            Token::String(s) => return write!(f, "string \"{}\"", s),
+            Token::EOF => return write!(f, "end of file"),
+            Token::Var => "var",
+            Token::Fn => "fn",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::For => "for",
+            Token::Switch => "switch",
+            Token::Case => "case",
+            Token::Default => "default",
+            Token::Out => "out",
+            Token::Return => "return",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Do => "do",
+            Token::Loop => "loop",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Null => "null",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Multiply => "*",
+            Token::Divide => "/",
+            Token::Assign => "=",
+            Token::Equals => "==",
+            Token::NotEquals => "!=",
+            Token::Greater => ">",
+            Token::Less => "<",
+            Token::GreaterEquals => ">=",
+            Token::LessEquals => "<=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Not => "!",
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::Dot => ".",
+        };
+        write!(f, "'{}'", symbol)
+    }
 } 
\ No newline at end of file
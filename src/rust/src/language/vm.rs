@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use super::bytecode::{Closure, FunctionProto, OpCode};
+use super::error::{RuntimeError, ScriptError};
+use super::interpreter::{Interpreter, Value};
+use super::collector;
+
+// Lokale Variable eines Aufrufs. Wird sie von einer Closure eingefangen,
+// wandert ihr Wert in eine geteilte Zelle.
+enum LocalSlot {
+    Value(Value),
+    Captured(Arc<Mutex<Value>>),
+}
+
+
+struct CallFrame {
+    closure: Arc<Closure>,
+    ip: usize,
+    locals: Vec<LocalSlot>,
+    stack_base: usize,
+}
+
+// Am Ende des Aufrufs erst die übrigen Lokalen freigeben, dann alle Zellen gemeinsam
+// prüfen: Zyklen können über mehrere Zellen laufen (wechselseitige Rekursion)
+impl Drop for CallFrame {
+    fn drop(&mut self) {
+        let cells: Vec<Arc<Mutex<Value>>> = self.locals
+            .drain(..)
+            .filter_map(|slot| match slot {
+                LocalSlot::Captured(cell) => Some(cell),
+                LocalSlot::Value(_) => None,
+            })
+            .collect();
+        collector::release_cells(cells.iter());
+    }
+}
+
+// Stack-basierte VM für den Bytecode aus dem `Compiler`. Globale Variablen und
+// native Funktionen kommen aus dem `Interpreter`, damit beide Backends dieselbe
+// Umgebung teilen.
+pub struct Vm<'a> {
+    interpreter: &'a mut Interpreter,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    result: Value,
+    base_depth: usize,  // Aufruftiefe beim Start, die Frames liegen auf dem Heap
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        let base_depth = interpreter.budget().depth();
+        Vm {
+            interpreter,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            result: Value::Null,
+            base_depth,
+        }
+    }
+
+    pub fn run_script(&mut self, script: Arc<FunctionProto>) -> Result<Value, ScriptError> {
+        let closure = Arc::new(Closure {
+            function: script,
+            upvalues: Vec::new(),
+        });
+        self.push_frame(closure, Vec::new());
+        self.run()
+    }
+
+    pub fn call_closure(&mut self, closure: Arc<Closure>, args: Vec<Value>) -> Result<Value, ScriptError> {
+        Self::check_arity(&closure, args.len())?;
+        self.interpreter.budget().enter_call()?;
+        let name = closure.function.name.clone();
+        self.push_frame(closure, args);
+        self.run().map_err(|error| error.leave_function(&name))
+    }
+
+    fn check_arity(closure: &Closure, got: usize) -> Result<(), RuntimeError> {
+        let expected = closure.function.arity;
+        if got != expected {
+            return Err(RuntimeError::InvalidArgumentCount { expected, got });
+        }
+        Ok(())
+    }
+
+    fn push_frame(&mut self, closure: Arc<Closure>, args: Vec<Value>) {
+        let local_count = closure.function.local_count.max(args.len());
+        let mut locals: Vec<LocalSlot> = args.into_iter().map(LocalSlot::Value).collect();
+        locals.resize_with(local_count, || LocalSlot::Value(Value::Null));
+        self.frames.push(CallFrame {
+            closure,
+            ip: 0,
+            locals,
+            stack_base: self.stack.len(),
+        });
+    }
+
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("call frame")
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("stack underflow")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack.last().expect("stack underflow")
+    }
+
+    fn run(&mut self) -> Result<Value, ScriptError> {
+        let result = self.execute();
+        self.interpreter.budget().unwind_to(self.base_depth);
+        result.map_err(|error| self.trace(error))
+    }
+
+    // Fehler ohne genauere Position bekommen den Bereich der Instruktion, an der die VM
+    // stand. Jeder offene Frame über dem ersten wird als verlassene Funktion vermerkt;
+    // den ersten vermerkt, wer die VM gestartet hat.
+    fn trace(&self, mut error: ScriptError) -> ScriptError {
+        for (depth, frame) in self.frames.iter().enumerate().rev() {
+            error = error.or_span(frame.closure.function.chunk.spans[frame.ip - 1]);
+            if depth > 0 {
+                error = error.leave_function(&frame.closure.function.name);
+            }
+        }
+        error
+    }
+
+    fn execute(&mut self) -> Result<Value, ScriptError> {
+        loop {
+            let frame = self.frame();
+            let op = frame.closure.function.chunk.code[frame.ip];
+            frame.ip += 1;
+            self.interpreter.step()?;
+
+            match op {
+                OpCode::Constant(index) => {
+                    let value = self.frame().closure.function.chunk.constants[index].clone();
+                    self.stack.push(value);
+                },
+                OpCode::Null => self.stack.push(Value::Null),
+                OpCode::True => self.stack.push(Value::Boolean(true)),
+                OpCode::False => self.stack.push(Value::Boolean(false)),
+                OpCode::Pop => {
+                    self.pop();
+                },
+                OpCode::Dup => {
+                    let value = self.peek().clone();
+                    self.stack.push(value);
+                },
+                OpCode::SetResult => {
+                    self.result = self.pop();
+                },
+                OpCode::DefineGlobal(index) => {
+                    let name = self.frame().closure.function.chunk.names[index].clone();
+                    let value = self.pop();
+                    self.interpreter.globals().lock().unwrap().define(name, value);
+                },
+                OpCode::GetGlobal(index) => {
+                    let value = {
+                        let frame = self.frames.last().expect("call frame");
+                        let name = &frame.closure.function.chunk.names[index];
+                        self.interpreter.globals().lock().unwrap().get(name)?
+                    };
+                    self.stack.push(value);
+                },
+                OpCode::SetGlobal(index) => {
+                    let value = self.peek().clone();
+                    let frame = self.frames.last().expect("call frame");
+                    let name = &frame.closure.function.chunk.names[index];
+                    self.interpreter.globals().lock().unwrap().assign(name, value)?;
+                },
+                OpCode::DefineLocal(slot) => {
+                    // Jede Deklaration erzeugt eine frische Variable, auch wenn der Slot wiederverwendet wird
+                    let value = self.pop();
+                    let previous = std::mem::replace(&mut self.frame().locals[slot], LocalSlot::Value(value));
+                    // Die alte Zelle kann nur noch über Closures erreichbar sein, die in ihr selbst liegen
+                    if let LocalSlot::Captured(cell) = previous {
+                        collector::release_cells(std::iter::once(&cell));
+                    }
+                },
+                OpCode::GetLocal(slot) => {
+                    let value = match &self.frame().locals[slot] {
+                        LocalSlot::Value(value) => value.clone(),
+                        LocalSlot::Captured(cell) => cell.lock().unwrap().clone(),
+                    };
+                    self.stack.push(value);
+                },
+                OpCode::SetLocal(slot) => {
+                    let value = self.peek().clone();
+                    match &mut self.frame().locals[slot] {
+                        LocalSlot::Value(local) => *local = value,
+                        LocalSlot::Captured(cell) => *cell.lock().unwrap() = value,
+                    }
+                },
+                OpCode::GetUpvalue(index) => {
+                    let value = self.frame().closure.upvalues[index].lock().unwrap().clone();
+                    self.stack.push(value);
+                },
+                OpCode::SetUpvalue(index) => {
+                    let value = self.peek().clone();
+                    *self.frame().closure.upvalues[index].lock().unwrap() = value;
+                },
+                OpCode::Binary(operator) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = self.interpreter.evaluate_binary_op(operator, left, right)?;
+                    self.stack.push(value);
+                },
+                OpCode::Unary(operator) => {
+                    let operand = self.pop();
+                    let value = self.interpreter.evaluate_unary_op(operator, operand)?;
+                    self.stack.push(value);
+                },
+                OpCode::Jump(target) => {
+                    self.frame().ip = target;
+                },
+                OpCode::PopJumpIfFalse(target) => {
+                    let condition = self.pop();
+                    if !self.interpreter.is_truthy(&condition) {
+                        self.frame().ip = target;
+                    }
+                },
+                OpCode::JumpIfFalseOrPop(target) => {
+                    if self.interpreter.is_truthy(self.peek()) {
+                        self.pop();
+                    } else {
+                        self.frame().ip = target;
+                    }
+                },
+                OpCode::JumpIfTrueOrPop(target) => {
+                    if self.interpreter.is_truthy(self.peek()) {
+                        self.frame().ip = target;
+                    } else {
+                        self.pop();
+                    }
+                },
+                OpCode::Call(argc) => {
+                    let callee_index = self.stack.len() - argc - 1;
+                    let args = self.stack.split_off(callee_index + 1);
+                    match self.pop() {
+                        Value::Closure(closure) => {
+                            Self::check_arity(&closure, args.len())?;
+                            self.interpreter.budget().enter_call()?;
+                            self.push_frame(closure, args);
+                        },
+                        // Native und interpretierte Funktionen laufen über den Interpreter
+                        callee => {
+                            let value = self.interpreter.call_value(callee, args)?;
+                            self.stack.push(value);
+                        },
+                    }
+                },
+                OpCode::Closure(index) => {
+                    let closure = self.make_closure(index);
+                    self.stack.push(Value::Closure(closure));
+                },
+                OpCode::Return => {
+                    let value = self.pop();
+                    let frame = self.frames.pop().expect("call frame");
+                    self.stack.truncate(frame.stack_base);
+                    if self.frames.is_empty() {
+                        return Ok(value);
+                    }
+                    self.interpreter.budget().exit_call();
+                    self.stack.push(value);
+                },
+                OpCode::Halt => {
+                    self.frames.clear();
+                    return Ok(std::mem::replace(&mut self.result, Value::Null));
+                },
+                OpCode::BuildArray(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count);
+                    self.stack.push(Value::array(elements));
+                },
+                OpCode::BuildMap(count) => {
+                    let entries = self.stack.split_off(self.stack.len() - count * 2);
+                    let mut map = BTreeMap::new();
+                    let mut entries = entries.into_iter();
+                    while let (Some(key), Some(value)) = (entries.next(), entries.next()) {
+                        if let Value::String(key) = key {
+                            map.insert(key, value);
+                        }
+                    }
+                    self.stack.push(Value::map(map));
+                },
+                OpCode::GetIndex => {
+                    let index = self.pop();
+                    let object = self.pop();
+                    let value = self.interpreter.read_index(object, index)?;
+                    self.stack.push(value);
+                },
+                OpCode::SetIndex => {
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.interpreter.assign_index(object, index, value.clone())?;
+                    self.stack.push(value);
+                },
+            }
+        }
+    }
+
+    fn make_closure(&mut self, index: usize) -> Arc<Closure> {
+        let frame = self.frame();
+        let function = frame.closure.function.chunk.functions[index].clone();
+
+        let mut upvalues = Vec::with_capacity(function.upvalues.len());
+        for upvalue in &function.upvalues {
+            let cell = if upvalue.is_local {
+                // Beim ersten Einfangen wandert die lokale Variable in eine Zelle
+                let slot = &mut frame.locals[upvalue.index];
+                match slot {
+                    LocalSlot::Captured(cell) => cell.clone(),
+                    LocalSlot::Value(value) => {
+                        let cell = Arc::new(Mutex::new(std::mem::replace(value, Value::Null)));
+                        *slot = LocalSlot::Captured(cell.clone());
+                        cell
+                    },
+                }
+            } else {
+                frame.closure.upvalues[upvalue.index].clone()
+            };
+            upvalues.push(cell);
+        }
+
+        Arc::new(Closure { function, upvalues })
+    }
+}
@@ -1,16 +1,21 @@
 #[macro_use]
 extern crate napi_derive;
 
-use napi::{Error, Result as NapiResult, Status};
+use napi::{Error, Status};
 use serde::{Deserialize, Serialize};
-use sysinfo::{ProcessExt, System, SystemExt, PidExt, CpuExt};
-use napi::threadsafe_function::{ThreadsafeFunction, ErrorStrategy};
-use std::sync::Arc;
-use std::sync::Mutex;
-use once_cell::sync::OnceCell;
 
 // Language-Modul einbinden
 pub mod language;
+pub mod execution;
+pub mod session;
+pub mod process_builtins;
+pub mod monitor;
+pub mod sampler;
+pub mod events;
+pub mod history;
+pub mod persistence;
+
+use monitor::{monitor, ProcessSample};
 
 // Struct to represent process information
 #[derive(Serialize, Deserialize)]
@@ -30,140 +35,68 @@ struct SystemInfo {
     total_cpu_usage: f32,
 }
 
-// Create a static System instance to reuse between calls
-static mut SYSTEM: Option<System> = None;
-
-// Initialize the system monitoring
-fn get_system() -> &'static mut System {
-    #[allow(static_mut_refs)]
-    unsafe {
-        if SYSTEM.is_none() {
-            SYSTEM = Some(System::new_all());
+impl From<ProcessSample> for ProcessInfo {
+    fn from(sample: ProcessSample) -> Self {
+        ProcessInfo {
+            pid: sample.pid,
+            name: sample.name,
+            memory_usage_kb: sample.memory,
+            cpu_usage_percent: sample.cpu_usage,
         }
-        SYSTEM.as_mut().unwrap()
     }
 }
 
-// Find a specific process by name
+fn process_list(samples: Vec<ProcessSample>) -> Vec<ProcessInfo> {
+    samples.into_iter().map(ProcessInfo::from).collect()
+}
+
+// Find a specific process by name. The return type is spelled out as `napi::Result`
+// so the macro throws the error instead of returning it as a value.
 #[napi]
-pub fn find_process(process_name: String) -> NapiResult<String> {
-    let sys = get_system();
-    sys.refresh_processes();
-
-    for (pid, process) in sys.processes() {
-        if process.name().to_lowercase() == process_name.to_lowercase() {
-            let process_info = ProcessInfo {
-                pid: pid.as_u32(),
-                name: process.name().to_string(),
-                memory_usage_kb: process.memory(),
-                cpu_usage_percent: process.cpu_usage(),
-            };
-
-            return Ok(serde_json::to_string(&process_info).unwrap());
-        }
+pub fn find_process(process_name: String) -> napi::Result<String> {
+    match monitor().find_process(&process_name) {
+        Some(sample) => Ok(serde_json::to_string(&ProcessInfo::from(sample)).unwrap()),
+        None => Err(Error::new(
+            Status::GenericFailure,
+            format!("Process '{}' not found", process_name),
+        )),
     }
-
-    Err(Error::new(
-        Status::GenericFailure,
-        format!("Process '{}' not found", process_name),
-    ))
 }
 
 // Get information about a specific process by PID
 #[napi]
-pub fn get_process_info(pid: u32) -> NapiResult<String> {
-    let sys = get_system();
-    sys.refresh_processes();
-
-    let pid = sysinfo::Pid::from_u32(pid);
-    if let Some(process) = sys.process(pid) {
-        let process_info = ProcessInfo {
-            pid: pid.as_u32(),
-            name: process.name().to_string(),
-            memory_usage_kb: process.memory(),
-            cpu_usage_percent: process.cpu_usage(),
-        };
-
-        return Ok(serde_json::to_string(&process_info).unwrap());
+pub fn get_process_info(pid: u32) -> napi::Result<String> {
+    match monitor().process(pid) {
+        Some(sample) => Ok(serde_json::to_string(&ProcessInfo::from(sample)).unwrap()),
+        None => Err(Error::new(
+            Status::GenericFailure,
+            format!("Process with PID {} not found", pid),
+        )),
     }
-
-    Err(Error::new(
-        Status::GenericFailure,
-        format!("Process with PID {} not found", pid),
-    ))
 }
 
 // Get all running processes
 #[napi]
 pub fn get_all_processes() -> String {
-    let sys = get_system();
-    sys.refresh_processes();
-
-    let mut processes = Vec::new();
-
-    for (pid, process) in sys.processes() {
-        let process_info = ProcessInfo {
-            pid: pid.as_u32(),
-            name: process.name().to_string(),
-            memory_usage_kb: process.memory(),
-            cpu_usage_percent: process.cpu_usage(),
-        };
-        processes.push(process_info);
-    }
-
-    serde_json::to_string(&processes).unwrap()
+    serde_json::to_string(&process_list(monitor().processes())).unwrap()
 }
 
 // Search for processes by name pattern (case insensitive)
 #[napi]
 pub fn search_processes(name_pattern: String) -> String {
-    let sys = get_system();
-    sys.refresh_processes();
-
-    let mut matching_processes = Vec::new();
-    let name_pattern_lower = name_pattern.to_lowercase();
-
-    for (pid, process) in sys.processes() {
-        if process.name().to_lowercase().contains(&name_pattern_lower) {
-            let process_info = ProcessInfo {
-                pid: pid.as_u32(),
-                name: process.name().to_string(),
-                memory_usage_kb: process.memory(),
-                cpu_usage_percent: process.cpu_usage(),
-            };
-            matching_processes.push(process_info);
-        }
-    }
-
-    serde_json::to_string(&matching_processes).unwrap()
+    serde_json::to_string(&process_list(monitor().search_processes(&name_pattern))).unwrap()
 }
 
 // Get system information
 #[napi]
 pub fn get_system_info() -> String {
-    let sys = get_system();
-    sys.refresh_all();
-
-    let total_memory = sys.total_memory();
-    let used_memory = sys.used_memory();
-    let total_cpu_usage = sys.global_cpu_info().cpu_usage();
-
-    let mut processes = Vec::new();
-    for (pid, process) in sys.processes() {
-        let process_info = ProcessInfo {
-            pid: pid.as_u32(),
-            name: process.name().to_string(),
-            memory_usage_kb: process.memory(),
-            cpu_usage_percent: process.cpu_usage(),
-        };
-        processes.push(process_info);
-    }
+    let sample = monitor().system();
 
     let system_info = SystemInfo {
-        processes,
-        total_memory,
-        used_memory,
-        total_cpu_usage,
+        processes: process_list(sample.processes),
+        total_memory: sample.total_memory,
+        used_memory: sample.used_memory,
+        total_cpu_usage: sample.cpu_usage,
     };
 
     serde_json::to_string(&system_info).unwrap()
@@ -175,29 +108,6 @@ pub fn add_numbers(a: i32, b: i32) -> i32 {
     a + b
 }
 
-#[napi]
-pub fn execute_code(
-    code: String,
-    callback: ThreadsafeFunction<String, ErrorStrategy::Fatal>,
-) -> Result<String, napi::Error> {
-    // Erstelle einen Klon des Callbacks für die Verwendung im Interpreter
-    let tsfn = callback.clone();
-    
-    let mut parser = language::Parser::new(&code);
-    let statements = match parser.parse_program() {
-        Ok(stmts) => stmts,
-        Err(e) => return Err(napi::Error::from_reason(format!("Parse Error: {}", e)))
-    };
-
-    // Erstelle den Interpreter mit dem geklonten Callback
-    let mut interpreter = language::Interpreter::with_callback(tsfn);
-    
-    match interpreter.interpret(statements) {
-        Ok(_) => Ok("Code successfully executed.".to_string()),
-        Err(e) => Err(napi::Error::from_reason(format!("Runtime Error: {}", e)))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     // Language-Tests
@@ -0,0 +1,290 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use once_cell::sync::Lazy;
+use sysinfo::{CpuExt, Pid, PidExt, Process, ProcessExt, System, SystemExt};
+
+use crate::history::{History, DEFAULT_CAPACITY};
+
+// Gemeinsame Prozessüberwachung für alle napi-Funktionen und Skript-Builtins.
+// Eine einzige `System`-Instanz, damit sysinfo die CPU-Auslastung aus dem Abstand
+// zweier Aktualisierungen berechnen kann; der Zugriff ist über eine Mutex synchronisiert.
+pub struct Monitor {
+    system: Mutex<System>,
+    latest: RwLock<Option<Arc<Snapshot>>>,
+    sampled: AtomicBool,  // Läuft der Sampler, werden Abfragen aus `latest` beantwortet
+    observers: Mutex<Vec<(u64, SharedObserver)>>,
+    published: Mutex<u64>,  // Nummer der letzten Aufnahme, hält ihre Reihenfolge ohne die System-Sperre
+    next_observer: AtomicU64,
+    history: Mutex<History>,  // Jede Aufnahme wird hier festgehalten
+}
+
+// Wird nach jeder Aktualisierung mit der neuen Aufnahme aufgerufen, auf dem Thread des
+// Aufrufers von `sample`. Läuft ohne die Sperren von System, Beobachterliste und
+// Veröffentlichung, darf also Beobachter an- und abmelden; ein gerade laufender Aufruf
+// endet aber noch regulär. Aufnahmen kommen in ihrer Reihenfolge an; überholt eine neuere
+// Aufnahme eine ältere, wird die ältere ausgelassen.
+pub type Observer = Box<dyn FnMut(&Snapshot) + Send>;
+type SharedObserver = Arc<Mutex<ObserverSlot>>;
+
+struct ObserverSlot {
+    observer: Observer,
+    delivered: u64,  // Nummer der zuletzt zugestellten Aufnahme
+}
+
+static MONITOR: Lazy<Monitor> = Lazy::new(Monitor::new);
+
+pub fn monitor() -> &'static Monitor {
+    &MONITOR
+}
+
+// Momentaufnahme eines Prozesses; `memory` in Bytes, `cpu_usage` in Prozent
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    pub memory: u64,
+    pub cpu_usage: f32,
+}
+
+impl ProcessSample {
+    fn new(pid: &Pid, process: &Process) -> Self {
+        ProcessSample {
+            pid: pid.as_u32(),
+            name: process.name().to_string(),
+            memory: process.memory(),
+            cpu_usage: process.cpu_usage(),
+        }
+    }
+}
+
+// Systemweite Werte; Speicher in Bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemSample {
+    pub total_memory: u64,
+    pub used_memory: u64,
+    pub cpu_usage: f32,
+    pub processes: Vec<ProcessSample>,
+}
+
+// Alle Werte einer Aktualisierung, `timestamp` in Millisekunden seit der Unix-Epoche
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub timestamp: u64,
+    pub system: SystemSample,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as u64).unwrap_or(0)
+}
+
+impl Monitor {
+    fn new() -> Self {
+        Monitor {
+            system: Mutex::new(System::new_all()),
+            latest: RwLock::new(None),
+            sampled: AtomicBool::new(false),
+            observers: Mutex::new(Vec::new()),
+            published: Mutex::new(0),
+            next_observer: AtomicU64::new(1),
+            history: Mutex::new(History::new(DEFAULT_CAPACITY)),
+        }
+    }
+
+    // Eine Panik während einer Abfrage lässt die Instanz in einem gültigen Zustand zurück
+    fn lock(&self) -> MutexGuard<'_, System> {
+        self.system.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Aktualisiert Prozesse, Speicher und CPU und legt das Ergebnis als neueste Aufnahme ab.
+    // Die System-Sperre wird gegen die Veröffentlichungs-Sperre getauscht, bevor Verlauf und
+    // neueste Aufnahme gesetzt werden, so bleibt die Reihenfolge erhalten. Die Beobachter
+    // laufen erst danach ohne Sperre; langsame Beobachter (z.B. Schreiben auf Platte) halten
+    // daher weder Abfragen noch weitere Aufnahmen auf.
+    pub fn sample(&self) -> Arc<Snapshot> {
+        let mut system = self.lock();
+        system.refresh_processes();
+        system.refresh_memory();
+        system.refresh_cpu();
+        let snapshot = Arc::new(Snapshot {
+            timestamp: now_millis(),
+            system: SystemSample {
+                total_memory: system.total_memory(),
+                used_memory: system.used_memory(),
+                cpu_usage: system.global_cpu_info().cpu_usage(),
+                processes: system.processes()
+                    .iter()
+                    .map(|(pid, process)| ProcessSample::new(pid, process))
+                    .collect(),
+            },
+        });
+        let mut published = self.published.lock().unwrap_or_else(PoisonError::into_inner);
+        drop(system);
+
+        *published += 1;
+        let sequence = *published;
+        *self.latest.write().unwrap_or_else(PoisonError::into_inner) = Some(snapshot.clone());
+        self.history().record(&snapshot);
+        drop(published);
+
+        let observers: Vec<SharedObserver> = self.observers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(_, observer)| observer.clone())
+            .collect();
+        for observer in observers {
+            let mut slot = observer.lock().unwrap_or_else(PoisonError::into_inner);
+            if slot.delivered < sequence {
+                slot.delivered = sequence;
+                (slot.observer)(&snapshot);
+            }
+        }
+        snapshot
+    }
+
+    pub fn observe(&self, observer: Observer) -> u64 {
+        let id = self.next_observer.fetch_add(1, Ordering::Relaxed);
+        self.observers.lock().unwrap_or_else(PoisonError::into_inner).push((id, Arc::new(Mutex::new(ObserverSlot { observer, delivered: 0 }))));
+        id
+    }
+
+    pub fn unobserve(&self, id: u64) -> bool {
+        let mut observers = self.observers.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = observers.len();
+        observers.retain(|(observer, _)| *observer != id);
+        observers.len() < count
+    }
+
+    pub fn history(&self) -> MutexGuard<'_, History> {
+        self.history.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn latest(&self) -> Option<Arc<Snapshot>> {
+        self.latest.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    pub(crate) fn set_sampled(&self, sampled: bool) {
+        self.sampled.store(sampled, Ordering::Release);
+    }
+
+    // Aufnahme des Samplers, ohne Sampler eine frische Aktualisierung
+    fn current(&self) -> Arc<Snapshot> {
+        if self.sampled.load(Ordering::Acquire) {
+            if let Some(snapshot) = self.latest() {
+                return snapshot;
+            }
+        }
+        self.sample()
+    }
+
+    pub fn processes(&self) -> Vec<ProcessSample> {
+        self.current().system.processes.clone()
+    }
+
+    pub fn process(&self, pid: u32) -> Option<ProcessSample> {
+        self.current().system.processes.iter().find(|process| process.pid == pid).cloned()
+    }
+
+    // Erster Prozess mit genau diesem Namen, ohne Beachtung der Groß-/Kleinschreibung
+    pub fn find_process(&self, name: &str) -> Option<ProcessSample> {
+        let name = name.to_lowercase();
+        self.current().system.processes.iter().find(|process| process.name.to_lowercase() == name).cloned()
+    }
+
+    // Alle Prozesse, deren Name das Muster enthält, ohne Beachtung der Groß-/Kleinschreibung
+    pub fn search_processes(&self, pattern: &str) -> Vec<ProcessSample> {
+        let pattern = pattern.to_lowercase();
+        self.current().system.processes
+            .iter()
+            .filter(|process| process.name.to_lowercase().contains(&pattern))
+            .cloned()
+            .collect()
+    }
+
+    pub fn system(&self) -> SystemSample {
+        self.current().system.clone()
+    }
+}
+
+// Aufnahmen ohne echtes System für die Tests von Ereignissen, Verlauf und Speicherung
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::{ProcessSample, Snapshot, SystemSample};
+
+    pub fn process(pid: u32, name: &str, memory: u64, cpu_usage: f32) -> ProcessSample {
+        ProcessSample { pid, name: name.to_string(), memory, cpu_usage }
+    }
+
+    pub fn snapshot(timestamp: u64, processes: Vec<ProcessSample>) -> Snapshot {
+        Snapshot {
+            timestamp,
+            system: SystemSample { total_memory: 1000, used_memory: 500, cpu_usage: 2.5, processes },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_queries() {
+        let own_pid = std::process::id();
+        let workers: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(move || monitor().process(own_pid).map(|process| process.pid)))
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), Some(own_pid));
+        }
+
+        let own = monitor().process(own_pid).unwrap();
+        assert_eq!(monitor().find_process(&own.name.to_uppercase()).map(|process| process.name), Some(own.name));
+        assert!(monitor().system().total_memory >= monitor().system().used_memory);
+    }
+
+    #[test]
+    fn test_observer_can_unobserve_itself() {
+        static OBSERVED: Lazy<Monitor> = Lazy::new(Monitor::new);
+        static ID: AtomicU64 = AtomicU64::new(0);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let id = OBSERVED.observe(Box::new(move |_: &Snapshot| {
+            // Liefe der Beobachter unter der Sperre der Liste, bliebe das hier hängen
+            let _ = sender.send(OBSERVED.unobserve(ID.load(Ordering::Relaxed)));
+        }));
+        ID.store(id, Ordering::Relaxed);
+
+        OBSERVED.sample();
+        OBSERVED.sample();
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![true]);
+    }
+
+    #[test]
+    fn test_slow_observer_does_not_block_sampling() {
+        static OBSERVED: Lazy<Monitor> = Lazy::new(Monitor::new);
+        let (entered_sender, entered) = std::sync::mpsc::channel();
+        let (release_sender, release) = std::sync::mpsc::channel::<()>();
+        OBSERVED.observe(Box::new(move |_: &Snapshot| {
+            let _ = entered_sender.send(());
+            let _ = release.recv();
+        }));
+
+        let first = std::thread::spawn(|| OBSERVED.sample());
+        entered.recv().unwrap();
+        let blocked = OBSERVED.latest().unwrap();
+
+        // Während der Beobachter noch die erste Aufnahme bearbeitet, erscheint schon die zweite
+        let second = std::thread::spawn(|| OBSERVED.sample());
+        let started = std::time::Instant::now();
+        while Arc::ptr_eq(&OBSERVED.latest().unwrap(), &blocked) {
+            assert!(started.elapsed() < std::time::Duration::from_secs(10), "second sample was not published");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        // Auch die System-Sperre ist frei
+        drop(OBSERVED.lock());
+
+        drop(release_sender);
+        first.join().unwrap();
+        second.join().unwrap();
+    }
+}
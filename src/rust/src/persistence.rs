@@ -0,0 +1,652 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, Status, Task};
+use once_cell::sync::Lazy;
+
+use crate::history::{buckets, process_history, query_range, History, HistoryBucket, HistoryQuery, ProcessHistory};
+use crate::monitor::{monitor, now_millis, ProcessSample, Snapshot, SystemSample};
+use crate::sampler::sampler;
+
+// Dateiformat: Kopf `MAGIC`, danach Datensätze aus Typ (u8), Länge (u32) und Inhalt,
+// alle Zahlen little-endian. Jede Datei ist für sich lesbar; ein beim Absturz
+// abgeschnittener Kopf des letzten Datensatzes wird beim Lesen übergangen.
+const MAGIC: &[u8; 8] = b"NOIAHST1";
+
+// Obergrenze für einen Datensatz (eine Aufnahme mit rund einer Million Prozessen).
+// Größere Längen stammen aus beschädigten Dateien und werden nicht erst angelegt.
+const MAX_RECORD_BYTES: u64 = 16 * 1024 * 1024;
+
+// Inhalt: pid (u32), Name (UTF-8). Steht vor dem ersten Wert eines Prozesses in der Datei.
+const RECORD_NAME: u8 = 1;
+// Inhalt: Zeitstempel (u64), CPU (f32), belegter und gesamter Speicher (u64, u64),
+// Anzahl (u32) und je Prozess pid (u32), CPU (f32), Speicher (u64)
+const RECORD_SNAPSHOT: u8 = 2;
+
+const FILE_PREFIX: &str = "history-";
+const FILE_EXTENSION: &str = "bin";
+
+// Es gilt die zuerst erreichte Grenze: Eine Datei wird gelöscht, sobald sie älter als
+// `max_age` ist oder das Verzeichnis `max_total_bytes` überschreitet. Ohne Filter werden
+// nur Systemwerte geschrieben (rund 40 Bytes je Aufnahme), dann reichen die Voreinstellungen
+// für die vollen 7 Tage. Jeder Prozess kostet 16 Bytes je Aufnahme; alle Prozesse eines
+// Rechners füllen 256 MB bei einer Aufnahme pro Sekunde oft schon nach einem Tag.
+#[derive(Debug, Clone)]
+pub struct PersistenceOptions {
+    pub directory: PathBuf,
+    pub max_file_bytes: u64,  // Danach beginnt eine neue Datei
+    pub max_total_bytes: u64,  // Älteste Dateien werden gelöscht, bis das Verzeichnis passt
+    pub max_age: Option<Duration>,  // Dateien, deren Werte alle älter sind, werden gelöscht
+    pub process_filter: Option<String>,  // Nur Prozesse, deren Name dies enthält; "" für alle
+}
+
+impl PersistenceOptions {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        PersistenceOptions {
+            directory: directory.into(),
+            max_file_bytes: 8 * 1024 * 1024,
+            max_total_bytes: 256 * 1024 * 1024,
+            max_age: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            process_filter: None,
+        }
+    }
+}
+
+// Hängt Aufnahmen an die aktuelle Datei an und rotiert nach `max_file_bytes`
+pub struct HistoryWriter {
+    options: PersistenceOptions,
+    file: Option<(PathBuf, BufWriter<File>)>,
+    file_bytes: u64,
+    names: HashMap<u32, String>,  // Bereits in der aktuellen Datei beschriebene Prozesse
+}
+
+impl HistoryWriter {
+    pub fn new(options: PersistenceOptions) -> io::Result<Self> {
+        fs::create_dir_all(&options.directory)?;
+        let writer = HistoryWriter {
+            options,
+            file: None,
+            file_bytes: 0,
+            names: HashMap::new(),
+        };
+        writer.enforce_retention(now_millis())?;
+        Ok(writer)
+    }
+
+    pub fn append(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        if self.file.is_none() || self.file_bytes >= self.options.max_file_bytes {
+            self.rotate(snapshot.timestamp)?;
+        }
+
+        let filter = self.options.process_filter.as_ref().map(|filter| filter.to_lowercase());
+        let processes: Vec<&ProcessSample> = match &filter {
+            Some(filter) => snapshot.system.processes
+                .iter()
+                .filter(|process| process.name.to_lowercase().contains(filter))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut records = Vec::new();
+        for process in &processes {
+            if self.names.get(&process.pid) != Some(&process.name) {
+                let mut payload = process.pid.to_le_bytes().to_vec();
+                payload.extend_from_slice(process.name.as_bytes());
+                write_record(&mut records, RECORD_NAME, &payload);
+                self.names.insert(process.pid, process.name.clone());
+            }
+        }
+
+        let mut payload = Vec::with_capacity(32 + processes.len() * 16);
+        payload.extend_from_slice(&snapshot.timestamp.to_le_bytes());
+        payload.extend_from_slice(&snapshot.system.cpu_usage.to_le_bytes());
+        payload.extend_from_slice(&snapshot.system.used_memory.to_le_bytes());
+        payload.extend_from_slice(&snapshot.system.total_memory.to_le_bytes());
+        payload.extend_from_slice(&(processes.len() as u32).to_le_bytes());
+        for process in &processes {
+            payload.extend_from_slice(&process.pid.to_le_bytes());
+            payload.extend_from_slice(&process.cpu_usage.to_le_bytes());
+            payload.extend_from_slice(&process.memory.to_le_bytes());
+        }
+        write_record(&mut records, RECORD_SNAPSHOT, &payload);
+
+        // Ein Datensatz je Aufnahme, sofort geschrieben, damit nach einem Absturz wenig fehlt
+        let (_, file) = self.file.as_mut().expect("file is opened by rotate");
+        if let Err(error) = file.write_all(&records).and_then(|()| file.flush()) {
+            // Die Datei endet jetzt womöglich mitten in einem Datensatz; weitere Aufnahmen
+            // dahinter wären nicht mehr lesbar. Der nächste Aufruf beginnt eine neue Datei.
+            self.file = None;
+            return Err(error);
+        }
+        self.file_bytes += records.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self, timestamp: u64) -> io::Result<()> {
+        if let Some((_, mut file)) = self.file.take() {
+            file.flush()?;
+        }
+
+        // Bei gleichem Zeitstempel (z.B. sehr kleines `max_file_bytes`) nicht überschreiben
+        let mut start = timestamp;
+        let path = loop {
+            let path = self.options.directory.join(file_name(start));
+            if !path.exists() {
+                break path;
+            }
+            start += 1;
+        };
+
+        let mut file = BufWriter::new(OpenOptions::new().create_new(true).write(true).open(&path)?);
+        file.write_all(MAGIC)?;
+        self.file = Some((path, file));
+        self.file_bytes = MAGIC.len() as u64;
+        self.names.clear();
+        self.enforce_retention(timestamp)
+    }
+
+    // Löscht die ältesten abgeschlossenen Dateien; die aktuelle bleibt immer erhalten
+    fn enforce_retention(&self, now: u64) -> io::Result<()> {
+        let files = history_files(&self.options.directory)?;
+        let current = self.file.as_ref().map(|(path, _)| path);
+        let mut total: u64 = files.iter().map(|file| file.bytes).sum();
+
+        for (i, file) in files.iter().enumerate() {
+            if Some(&file.path) == current {
+                break;
+            }
+            // Eine Datei endet, wo die nächste beginnt
+            let end = files.get(i + 1).map(|next| next.start).unwrap_or(u64::MAX);
+            let expired = self.options.max_age.is_some_and(|max_age| end < now.saturating_sub(max_age.as_millis() as u64));
+            if !expired && total <= self.options.max_total_bytes {
+                break;
+            }
+            fs::remove_file(&file.path)?;
+            total -= file.bytes;
+        }
+        Ok(())
+    }
+}
+
+fn write_record(buffer: &mut Vec<u8>, kind: u8, payload: &[u8]) {
+    buffer.push(kind);
+    buffer.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(payload);
+}
+
+fn file_name(start: u64) -> String {
+    // Feste Breite, damit die Namen nach Zeit sortiert sind
+    format!("{}{:016}.{}", FILE_PREFIX, start, FILE_EXTENSION)
+}
+
+struct HistoryFile {
+    start: u64,
+    path: PathBuf,
+    bytes: u64,
+}
+
+// Verlaufsdateien im Verzeichnis, nach Beginn sortiert; andere Dateien werden ignoriert
+fn history_files(directory: &Path) -> io::Result<Vec<HistoryFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = entry.path();
+        let start = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|_| path.extension().is_some_and(|extension| extension == FILE_EXTENSION))
+            .and_then(|stem| stem.strip_prefix(FILE_PREFIX))
+            .and_then(|start| start.parse::<u64>().ok());
+        if let Some(start) = start {
+            files.push(HistoryFile { start, path, bytes: entry.metadata()?.len() });
+        }
+    }
+    files.sort_by_key(|file| file.start);
+    Ok(files)
+}
+
+// Liest Felder aus dem Inhalt eines Datensatzes; None, wenn er zu kurz ist
+struct Fields<'a>(&'a [u8]);
+
+impl Fields<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        if self.0.len() < N {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(N);
+        self.0 = rest;
+        head.try_into().ok()
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+}
+
+fn parse_snapshot(payload: &[u8], names: &HashMap<u32, String>) -> Option<Snapshot> {
+    let mut fields = Fields(payload);
+    let timestamp = fields.u64()?;
+    let cpu_usage = fields.f32()?;
+    let used_memory = fields.u64()?;
+    let total_memory = fields.u64()?;
+    let count = fields.u32()?;
+
+    let mut processes = Vec::new();
+    for _ in 0..count {
+        let pid = fields.u32()?;
+        let cpu_usage = fields.f32()?;
+        let memory = fields.u64()?;
+        let name = names.get(&pid).cloned().unwrap_or_default();
+        processes.push(ProcessSample { pid, name, memory, cpu_usage });
+    }
+    Some(Snapshot {
+        timestamp,
+        system: SystemSample { total_memory, used_memory, cpu_usage, processes },
+    })
+}
+
+// Ruft `visit` für jede Aufnahme der Datei auf. Endet die Datei mitten in einem Datensatz
+// (abgeschnitten beim Absturz oder gerade erst angehängt), endet das Lesen dort ohne Fehler.
+// Ein falscher Dateikopf oder eine Länge über `MAX_RECORD_BYTES` ist ein Fehler (`InvalidData`).
+fn read_file(path: &Path, mut visit: impl FnMut(Snapshot)) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 8];
+    // Eine eben angelegte Datei hat noch keinen vollständigen Kopf
+    if !read_complete(&mut reader, &mut magic)? {
+        return Ok(());
+    }
+    if &magic != MAGIC {
+        return Err(io::Error::new(ErrorKind::InvalidData, "not a history file"));
+    }
+
+    let mut names = HashMap::new();
+    loop {
+        let mut header = [0u8; 5];
+        if !read_complete(&mut reader, &mut header)? {
+            return Ok(());
+        }
+
+        let length = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as u64;
+        if length > MAX_RECORD_BYTES {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("record of {} bytes exceeds the limit", length),
+            ));
+        }
+        let mut payload = vec![0u8; length as usize];
+        if !read_complete(&mut reader, &mut payload)? {
+            return Ok(());
+        }
+
+        match header[0] {
+            RECORD_NAME if payload.len() >= 4 => {
+                let pid = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                names.insert(pid, String::from_utf8_lossy(&payload[4..]).into_owned());
+            },
+            RECORD_SNAPSHOT => {
+                if let Some(snapshot) = parse_snapshot(&payload, &names) {
+                    visit(snapshot);
+                }
+            },
+            // Unbekannte Datensätze späterer Versionen überspringen
+            _ => {},
+        }
+    }
+}
+
+// Füllt `buffer` ganz; false, wenn die Datei vorher endet
+fn read_complete(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+// Ergebnis von `read_history`. Dateien, die sich nicht lesen lassen, werden übersprungen;
+// ihre bis dahin gelesenen Aufnahmen bleiben im Verlauf.
+pub struct LoadedHistory {
+    pub history: History,
+    pub skipped: Vec<String>,  // Je Datei Pfad und Fehler
+}
+
+// Lädt die Aufnahmen mit `from <= timestamp <= to` in einen Verlauf, der sich wie der
+// laufende abfragen lässt. Wie dort bleiben höchstens `capacity` Werte je Reihe, die neuesten.
+pub fn read_history(directory: &Path, from: u64, to: u64, capacity: usize) -> io::Result<LoadedHistory> {
+    let files = history_files(directory)?;
+    let mut history = History::new(capacity);
+    let mut skipped = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let end = files.get(i + 1).map(|next| next.start).unwrap_or(u64::MAX);
+        if file.start > to || end < from {
+            continue;
+        }
+        let result = read_file(&file.path, |snapshot| {
+            if snapshot.timestamp >= from && snapshot.timestamp <= to {
+                history.record(&snapshot);
+            }
+        });
+        if let Err(error) = result {
+            skipped.push(format!("{}: {}", file.path.display(), error));
+        }
+    }
+    Ok(LoadedHistory { history, skipped })
+}
+
+struct ActivePersistence {
+    observer: u64,
+    writer: Arc<Mutex<HistoryWriter>>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+static PERSISTENCE: Lazy<Mutex<Option<ActivePersistence>>> = Lazy::new(|| Mutex::new(None));
+
+// Fehlende Werte wie in `PersistenceOptions::new`, also ohne Filter nur Systemwerte
+#[napi(object)]
+pub struct HistoryPersistenceOptions {
+    pub directory: String,
+    pub max_file_bytes: Option<f64>,
+    pub max_total_bytes: Option<f64>,
+    pub max_age_ms: Option<f64>,  // 0: kein Höchstalter
+    pub process_filter: Option<String>,  // "" für alle Prozesse
+}
+
+#[napi(object)]
+pub struct HistoryPersistenceStatus {
+    pub active: bool,
+    pub directory: Option<String>,
+    pub last_error: Option<String>,
+}
+
+fn io_error(error: io::Error) -> Error {
+    Error::new(Status::GenericFailure, error.to_string())
+}
+
+// Schreibt jede Aufnahme des Samplers in das Verzeichnis; der Sampler läuft bis zum Stopp mit.
+// Läuft bereits eine Aufzeichnung, wird sie durch die neue ersetzt.
+#[napi]
+pub fn start_history_persistence(options: HistoryPersistenceOptions) -> napi::Result<()> {
+    let mut persistence_options = PersistenceOptions::new(options.directory);
+    if let Some(max_file_bytes) = options.max_file_bytes {
+        persistence_options.max_file_bytes = max_file_bytes.max(0.0) as u64;
+    }
+    if let Some(max_total_bytes) = options.max_total_bytes {
+        persistence_options.max_total_bytes = max_total_bytes.max(0.0) as u64;
+    }
+    if let Some(max_age_ms) = options.max_age_ms {
+        persistence_options.max_age = (max_age_ms > 0.0).then(|| Duration::from_millis(max_age_ms as u64));
+    }
+    persistence_options.process_filter = options.process_filter;
+
+    let writer = Arc::new(Mutex::new(HistoryWriter::new(persistence_options).map_err(io_error)?));
+    let last_error = Arc::new(Mutex::new(None));
+
+    let observer = {
+        let writer = writer.clone();
+        let last_error = last_error.clone();
+        monitor().observe(Box::new(move |snapshot: &Snapshot| {
+            let result = writer.lock().unwrap_or_else(PoisonError::into_inner).append(snapshot);
+            // Ein Schreibfehler (z.B. volle Platte) beendet die Aufzeichnung nicht: `append`
+            // gibt die betroffene Datei auf und beginnt mit der nächsten Aufnahme eine neue
+            *last_error.lock().unwrap_or_else(PoisonError::into_inner) = result.err().map(|error| error.to_string());
+        }))
+    };
+
+    let previous = PERSISTENCE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(ActivePersistence { observer, writer, last_error });
+    // Die ersetzte Aufzeichnung gibt ihren Anteil am Sampler an die neue weiter
+    match previous {
+        Some(previous) => {
+            monitor().unobserve(previous.observer);
+        },
+        None => sampler().acquire(),
+    }
+    Ok(())
+}
+
+// false, wenn keine Aufzeichnung lief. Gibt den Sampler frei, den die Aufzeichnung nutzte.
+#[napi]
+pub fn stop_history_persistence() -> bool {
+    let active = PERSISTENCE.lock().unwrap_or_else(PoisonError::into_inner).take();
+    match active {
+        Some(active) => {
+            monitor().unobserve(active.observer);
+            sampler().release();
+            true
+        },
+        None => false,
+    }
+}
+
+#[napi]
+pub fn get_history_persistence_status() -> HistoryPersistenceStatus {
+    match PERSISTENCE.lock().unwrap_or_else(PoisonError::into_inner).as_ref() {
+        Some(active) => HistoryPersistenceStatus {
+            active: true,
+            directory: Some(active.writer.lock().unwrap_or_else(PoisonError::into_inner).options.directory.display().to_string()),
+            last_error: active.last_error.lock().unwrap_or_else(PoisonError::into_inner).clone(),
+        },
+        None => HistoryPersistenceStatus { active: false, directory: None, last_error: None },
+    }
+}
+
+#[napi(object)]
+pub struct ArchivedProcess {
+    pub pid: u32,
+    pub name: String,
+    pub first: f64,
+    pub last: f64,
+}
+
+// Aus Dateien geladener Verlauf mit denselben Abfragen wie der laufende
+#[napi]
+pub struct HistoryArchive {
+    history: History,
+    skipped: Vec<String>,
+}
+
+#[napi]
+impl HistoryArchive {
+    // Enthaltene Prozesse, optional nach einem Teil des Namens gefiltert
+    #[napi]
+    pub fn processes(&self, name: Option<String>) -> Vec<ArchivedProcess> {
+        let name = name.map(|name| name.to_lowercase());
+        let mut processes: Vec<ArchivedProcess> = self.history
+            .processes()
+            .filter(|(_, process)| name.as_ref().is_none_or(|name| process.name.to_lowercase().contains(name)))
+            .map(|(pid, process)| ArchivedProcess {
+                pid,
+                name: process.name.clone(),
+                first: process.series.oldest().unwrap_or(0) as f64,
+                last: process.series.newest().unwrap_or(0) as f64,
+            })
+            .collect();
+        processes.sort_by_key(|process| process.pid);
+        processes
+    }
+
+    #[napi]
+    pub fn process_history(&self, pid: u32, query: Option<HistoryQuery>) -> napi::Result<Option<ProcessHistory>> {
+        Ok(process_history(&self.history, pid, &query_range(query)?))
+    }
+
+    #[napi]
+    pub fn system_history(&self, query: Option<HistoryQuery>) -> napi::Result<Vec<HistoryBucket>> {
+        Ok(buckets(self.history.system(), &query_range(query)?))
+    }
+
+    // Beschädigte oder unlesbare Dateien, die beim Laden übersprungen wurden, mit Fehler
+    #[napi]
+    pub fn skipped_files(&self) -> Vec<String> {
+        self.skipped.clone()
+    }
+}
+
+// Liest die Dateien im libuv-Threadpool, damit große Zeiträume den Haupt-Thread nicht blockieren
+pub struct LoadHistoryTask {
+    directory: PathBuf,
+    from: u64,
+    to: u64,
+    capacity: usize,
+}
+
+impl Task for LoadHistoryTask {
+    type Output = LoadedHistory;
+    type JsValue = HistoryArchive;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        read_history(&self.directory, self.from, self.to, self.capacity).map_err(io_error)
+    }
+
+    fn resolve(&mut self, _env: Env, loaded: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(HistoryArchive { history: loaded.history, skipped: loaded.skipped })
+    }
+}
+
+// Lädt den Zeitraum `from..=to` (Millisekunden seit der Unix-Epoche) aus einem Verzeichnis,
+// höchstens so viele Werte je Reihe wie der laufende Verlauf (`configureHistory`).
+// Gibt ein Promise auf das Archiv zurück.
+#[napi]
+pub fn load_history(directory: String, from: Option<f64>, to: Option<f64>) -> AsyncTask<LoadHistoryTask> {
+    AsyncTask::new(LoadHistoryTask {
+        directory: PathBuf::from(directory),
+        from: from.map(|from| from.max(0.0) as u64).unwrap_or(0),
+        to: to.map(|to| to.max(0.0) as u64).unwrap_or(u64::MAX),
+        capacity: monitor().history().capacity(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::fixtures::{process, snapshot};
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("noia-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn aion_snapshot(timestamp: u64, memory: u64) -> Snapshot {
+        snapshot(timestamp, vec![process(42, "aion.bin", memory, 1.5), process(7, "bash", 1, 0.0)])
+    }
+
+    #[test]
+    fn test_write_and_read_window() {
+        let directory = temp_directory("window");
+        let mut options = PersistenceOptions::new(&directory);
+        options.process_filter = Some("AION".to_string());
+        let mut writer = HistoryWriter::new(options).unwrap();
+        for timestamp in 1..=5 {
+            writer.append(&aion_snapshot(timestamp * 1000, timestamp * 100)).unwrap();
+        }
+        drop(writer);
+
+        let history = read_history(&directory, 2000, 4000, usize::MAX).unwrap().history;
+        let process = history.process(42).unwrap();
+        assert_eq!(process.name, "aion.bin");
+        let memory: Vec<u64> = process.series.range(0, u64::MAX).map(|point| point.memory).collect();
+        assert_eq!(memory, vec![200, 300, 400]);
+        assert!(history.process(7).is_none());
+        assert_eq!(history.system().range(0, u64::MAX).count(), 3);
+
+        // Ein abgeschnittener Kopf am Ende kostet nur diese Aufnahme
+        let file = &history_files(&directory).unwrap()[0];
+        let length = fs::metadata(&file.path).unwrap().len();
+        let mut tail = OpenOptions::new().append(true).open(&file.path).unwrap();
+        tail.write_all(&[RECORD_SNAPSHOT, 0]).unwrap();
+        assert_eq!(read_history(&directory, 0, u64::MAX, usize::MAX).unwrap().history.system().range(0, u64::MAX).count(), 5);
+
+        // Ebenso ein abgeschnittener letzter Datensatz; alles davor bleibt lesbar
+        tail.set_len(length - 3).unwrap();
+        let loaded = read_history(&directory, 0, u64::MAX, usize::MAX).unwrap();
+        let memory: Vec<u64> = loaded.history.process(42).unwrap().series.range(0, u64::MAX).map(|point| point.memory).collect();
+        assert_eq!(memory, vec![100, 200, 300, 400]);
+        assert!(loaded.skipped.is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_default_writes_system_values_only() {
+        let directory = temp_directory("default");
+        let mut writer = HistoryWriter::new(PersistenceOptions::new(&directory)).unwrap();
+        writer.append(&aion_snapshot(1000, 100)).unwrap();
+        drop(writer);
+
+        let history = read_history(&directory, 0, u64::MAX, usize::MAX).unwrap().history;
+        assert_eq!(history.system().range(0, u64::MAX).count(), 1);
+        assert_eq!(history.processes().count(), 0);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_and_retention() {
+        let directory = temp_directory("rotation");
+        let mut options = PersistenceOptions::new(&directory);
+        options.max_file_bytes = 1;  // Jede Aufnahme in eine eigene Datei
+        options.process_filter = Some(String::new());
+        options.max_total_bytes = u64::MAX;
+        options.max_age = Some(Duration::from_millis(2500));
+        let mut writer = HistoryWriter::new(options).unwrap();
+        for timestamp in 1..=6 {
+            writer.append(&aion_snapshot(timestamp * 1000, 100)).unwrap();
+        }
+
+        // Beim Start der Datei für 6000 sind die Dateien von 1000 und 2000 (Ende bis 3000 < 3500) abgelaufen
+        let starts: Vec<u64> = history_files(&directory).unwrap().iter().map(|file| file.start).collect();
+        assert_eq!(starts, vec![3000, 4000, 5000, 6000]);
+
+        // Jede Datei beschreibt ihre Prozesse selbst
+        let history = read_history(&directory, 0, u64::MAX, usize::MAX).unwrap().history;
+        assert_eq!(history.process(42).unwrap().series.range(0, u64::MAX).count(), 4);
+
+        // Wie der laufende Verlauf behält das Archiv nur die neuesten Werte
+        let history = read_history(&directory, 0, u64::MAX, 2).unwrap().history;
+        let timestamps: Vec<u64> = history.system().range(0, u64::MAX).map(|point| point.timestamp).collect();
+        assert_eq!(timestamps, vec![5000, 6000]);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_damaged_file_is_skipped() {
+        let directory = temp_directory("damaged");
+        let mut options = PersistenceOptions::new(&directory);
+        options.max_file_bytes = 1;
+        let mut writer = HistoryWriter::new(options).unwrap();
+        for timestamp in 1..=3 {
+            writer.append(&aion_snapshot(timestamp * 1000, 100)).unwrap();
+        }
+        drop(writer);
+
+        // Falscher Kopf in der mittleren, unsinnige Länge in der letzten Datei
+        let files = history_files(&directory).unwrap();
+        fs::write(&files[1].path, b"garbage!").unwrap();
+        let mut last = OpenOptions::new().append(true).open(&files[2].path).unwrap();
+        last.write_all(&[RECORD_SNAPSHOT, 0xff, 0xff, 0xff, 0xff]).unwrap();
+
+        let loaded = read_history(&directory, 0, u64::MAX, usize::MAX).unwrap();
+        let timestamps: Vec<u64> = loaded.history.system().range(0, u64::MAX).map(|point| point.timestamp).collect();
+        assert_eq!(timestamps, vec![1000, 3000]);
+        assert_eq!(loaded.skipped.len(), 2);
+        assert!(loaded.skipped[0].ends_with(": not a history file"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use crate::language::{Interpreter, NativeSignature, Value};
+use crate::monitor::{monitor, ProcessSample};
+
+type Entries = BTreeMap<String, Value>;
+
+// Prozess als Map: pid, name, memory (Bytes), cpu_usage (Prozent)
+fn process_entries(sample: ProcessSample) -> Entries {
+    let mut entries = BTreeMap::new();
+    entries.insert("pid".to_string(), Value::Number(sample.pid as f64));
+    entries.insert("name".to_string(), Value::String(sample.name));
+    entries.insert("memory".to_string(), Value::Number(sample.memory as f64));
+    entries.insert("cpu_usage".to_string(), Value::Number(sample.cpu_usage as f64));
+    entries
+}
+
+fn process_list(samples: Vec<ProcessSample>) -> Vec<Entries> {
+    samples.into_iter().map(process_entries).collect()
+}
+
+// Registriert die Builtins zur Prozessüberwachung. Anzahl und Typen der
+// Argumente prüft der Interpreter anhand der aus den Closures abgeleiteten Signaturen.
+pub fn register_process_builtins(interpreter: &mut Interpreter) {
+    interpreter.register_fn(
+        NativeSignature::new("find_process")
+            .names(&["name"])
+            .doc("First process with exactly this name (case-insensitive), or null."),
+        |name: String| monitor().find_process(&name).map(process_entries),
+    );
+
+    interpreter.register_fn(
+        NativeSignature::new("search_processes")
+            .names(&["pattern"])
+            .doc("All processes whose name contains the pattern (case-insensitive)."),
+        |pattern: String| process_list(monitor().search_processes(&pattern)),
+    );
+
+    interpreter.register_fn(
+        NativeSignature::new("processes").doc("All running processes."),
+        || process_list(monitor().processes()),
+    );
+
+    interpreter.register_fn(
+        NativeSignature::new("process_info")
+            .names(&["pid"])
+            .doc("The process with this id, or null."),
+        |pid: u32| monitor().process(pid).map(process_entries),
+    );
+
+    interpreter.register_fn(
+        NativeSignature::new("system_info")
+            .doc("System totals: total_memory, used_memory (bytes), cpu_usage (percent), process_count."),
+        || {
+            let sample = monitor().system();
+
+            let mut entries = BTreeMap::new();
+            entries.insert("total_memory".to_string(), Value::Number(sample.total_memory as f64));
+            entries.insert("used_memory".to_string(), Value::Number(sample.used_memory as f64));
+            entries.insert("cpu_usage".to_string(), Value::Number(sample.cpu_usage as f64));
+            entries.insert("process_count".to_string(), Value::Number(sample.processes.len() as f64));
+            entries
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::Parser;
+
+    fn evaluate(input: &str) -> Value {
+        let mut interpreter = Interpreter::new();
+        register_process_builtins(&mut interpreter);
+        interpreter.define_global("own_pid".to_string(), Value::Number(std::process::id() as f64));
+        let statements = Parser::new(input).parse_program().unwrap();
+        interpreter.interpret(statements).unwrap()
+    }
+
+    #[test]
+    fn test_process_info_for_own_process() {
+        assert_eq!(evaluate("process_info(own_pid).pid == own_pid;"), Value::Boolean(true));
+        assert_eq!(evaluate("var own = process_info(own_pid); find_process(own.name) != null;"), Value::Boolean(true));
+        assert_eq!(evaluate("process_info(4294967295);"), Value::Null);
+    }
+
+    #[test]
+    fn test_system_info_totals() {
+        assert_eq!(evaluate("var info = system_info(); info.total_memory >= info.used_memory;"), Value::Boolean(true));
+        assert_eq!(evaluate("len(processes()) > 0;"), Value::Boolean(true));
+    }
+}
@@ -0,0 +1,272 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use napi::{Error, Status};
+use once_cell::sync::Lazy;
+use sysinfo::{System, SystemExt};
+
+use crate::monitor::monitor;
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Aktualisiert die gemeinsame Prozessüberwachung in festem Takt auf einem eigenen
+// Thread. Die CPU-Werte hängen damit nicht mehr davon ab, wie oft die UI abfragt.
+// Der Thread läuft, solange ihn jemand braucht: Anmeldungen für Prozessereignisse,
+// die Speicherung auf Platte und ein expliziter Start zählen je als ein Nutzer.
+pub struct Sampler {
+    interval_ms: AtomicU64,  // Änderungen gelten ab dem nächsten Takt
+    state: Mutex<SamplerState>,
+}
+
+#[derive(Default)]
+struct SamplerState {
+    users: usize,
+    started: bool,  // Über `start` gestartet; zählt als einer der Nutzer
+    thread: Option<SamplerThread>,
+}
+
+// Der Thread wird beim Anhalten nicht abgewartet; eine laufende Aktualisierung
+// endet noch, danach beendet er sich selbst
+struct SamplerThread {
+    stop: Sender<()>,
+}
+
+static SAMPLER: Lazy<Sampler> = Lazy::new(|| Sampler::new(DEFAULT_INTERVAL));
+
+pub fn sampler() -> &'static Sampler {
+    &SAMPLER
+}
+
+impl Sampler {
+    // Privat: Der Sampler schaltet `Monitor::set_sampled` um, das gibt es nur einmal
+    fn new(interval: Duration) -> Self {
+        Sampler {
+            interval_ms: AtomicU64::new(interval.as_millis() as u64),
+            state: Mutex::new(SamplerState::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SamplerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.load(Ordering::Relaxed))
+    }
+
+    // Kürzere Abstände liefert sysinfo keine verlässlichen CPU-Werte
+    pub fn set_interval(&self, interval: Duration) -> Result<(), String> {
+        if interval < System::MINIMUM_CPU_UPDATE_INTERVAL {
+            return Err(format!(
+                "Interval must be at least {} ms",
+                System::MINIMUM_CPU_UPDATE_INTERVAL.as_millis()
+            ));
+        }
+        self.interval_ms.store(interval.as_millis() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.lock().thread.is_some()
+    }
+
+    pub fn users(&self) -> usize {
+        self.lock().users
+    }
+
+    // Meldet einen Nutzer an und startet den Thread, falls er noch nicht läuft.
+    // Zu jedem `acquire` gehört genau ein `release`.
+    pub fn acquire(&'static self) {
+        self.retain(&mut self.lock());
+    }
+
+    // Beendet den Thread, sobald der letzte Nutzer abgemeldet ist
+    pub fn release(&self) {
+        Self::dismiss(&mut self.lock());
+    }
+
+    // Expliziter Start, z.B. aus der UI; false, wenn bereits explizit gestartet
+    pub fn start(&'static self) -> bool {
+        let mut state = self.lock();
+        if state.started {
+            return false;
+        }
+        state.started = true;
+        self.retain(&mut state);
+        true
+    }
+
+    // Nimmt nur den expliziten Start zurück; Anmeldungen und Speicherung laufen weiter.
+    // false, wenn der Sampler nicht explizit gestartet war.
+    pub fn stop(&self) -> bool {
+        let mut state = self.lock();
+        if !state.started {
+            return false;
+        }
+        state.started = false;
+        Self::dismiss(&mut state);
+        true
+    }
+
+    fn retain(&'static self, state: &mut SamplerState) {
+        state.users += 1;
+        if state.thread.is_none() {
+            state.thread = Some(self.spawn());
+        }
+    }
+
+    fn dismiss(state: &mut SamplerState) {
+        state.users = state.users.saturating_sub(1);
+        if state.users > 0 {
+            return;
+        }
+        // Nicht auf den Thread warten: Eine laufende Aktualisierung (samt langsamer Beobachter)
+        // würde sonst den Aufrufer und über die Sperre jedes `acquire` aufhalten. Abfragen
+        // aktualisieren ab sofort wieder selbst.
+        if let Some(SamplerThread { stop }) = state.thread.take() {
+            drop(stop);
+            monitor().set_sampled(false);
+        }
+    }
+
+    fn spawn(&'static self) -> SamplerThread {
+        let (stop, stopped) = channel();
+        std::thread::spawn(move || {
+            // Erste Aktualisierung als Bezugspunkt; erst die nächste liefert CPU-Werte über
+            // ein volles Intervall. Abfragen lesen schon ab hier `latest`, damit sie den
+            // Abstand zwischen den Aktualisierungen nicht verkürzen.
+            monitor().sample();
+            {
+                // Unter der Sperre, damit ein inzwischen angehaltener Thread den Schalter
+                // nicht nach `dismiss` oder für seinen Nachfolger umlegt
+                let _state = self.lock();
+                if let Err(TryRecvError::Empty) = stopped.try_recv() {
+                    monitor().set_sampled(true);
+                }
+            }
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(self.interval()) {
+                monitor().sample();
+            }
+        });
+        SamplerThread { stop }
+    }
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct SamplerOptions {
+    pub interval_ms: Option<u32>,
+}
+
+#[napi(object)]
+pub struct SamplerStatus {
+    pub running: bool,
+    pub users: u32,  // Anmeldungen, Speicherung und expliziter Start
+    pub interval_ms: u32,
+    pub last_sample: Option<f64>,  // Millisekunden seit der Unix-Epoche
+}
+
+fn apply_options(options: Option<SamplerOptions>) -> napi::Result<()> {
+    if let Some(interval_ms) = options.unwrap_or_default().interval_ms {
+        sampler()
+            .set_interval(Duration::from_millis(interval_ms as u64))
+            .map_err(|message| Error::new(Status::InvalidArg, message))?;
+    }
+    Ok(())
+}
+
+// Startet die Hintergrundaktualisierung; false, wenn sie bereits explizit gestartet war
+#[napi]
+pub fn start_sampler(options: Option<SamplerOptions>) -> napi::Result<bool> {
+    apply_options(options)?;
+    Ok(sampler().start())
+}
+
+// Nimmt `start_sampler` zurück. Erst wenn auch keine Anmeldung und keine Speicherung
+// den Sampler mehr braucht, werden Abfragen wieder bei jedem Aufruf aktualisiert.
+#[napi]
+pub fn stop_sampler() -> bool {
+    sampler().stop()
+}
+
+#[napi]
+pub fn configure_sampler(options: SamplerOptions) -> napi::Result<()> {
+    apply_options(Some(options))
+}
+
+#[napi]
+pub fn get_sampler_status() -> SamplerStatus {
+    SamplerStatus {
+        running: sampler().is_running(),
+        users: sampler().users() as u32,
+        interval_ms: sampler().interval().as_millis() as u32,
+        last_sample: monitor().latest().map(|snapshot| snapshot.timestamp as f64),
+    }
+}
+
+// Tests, die den globalen Sampler starten oder seine Nutzer zählen, laufen nacheinander
+#[cfg(test)]
+pub(crate) static TEST_SERIAL: Mutex<()> = Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Instant;
+    use crate::monitor::Snapshot;
+    use super::*;
+
+    #[test]
+    fn test_start_and_stop() {
+        let _serial = TEST_SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+        assert!(sampler().set_interval(Duration::from_millis(1)).is_err());
+        sampler().set_interval(System::MINIMUM_CPU_UPDATE_INTERVAL).unwrap();
+
+        assert!(sampler().start());
+        assert!(!sampler().start());
+        // Schon die Bezugsaufnahme beantwortet Abfragen
+        std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL / 2);
+        let reference = monitor().latest().unwrap();
+        monitor().processes();
+        assert!(Arc::ptr_eq(&monitor().latest().unwrap(), &reference));
+
+        std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL * 3);
+        let latest = monitor().latest().unwrap();
+        assert!(latest.timestamp > reference.timestamp);
+        assert!(latest.system.processes.iter().any(|process| process.pid == std::process::id()));
+
+        // Ein weiterer Nutzer hält den Sampler über `stop` hinaus am Laufen
+        sampler().acquire();
+        assert!(sampler().stop());
+        assert!(!sampler().stop());
+        assert!(sampler().is_running());
+        sampler().release();
+        assert!(!sampler().is_running());
+        sampler().set_interval(DEFAULT_INTERVAL).unwrap();
+    }
+
+    #[test]
+    fn test_stop_during_sample() {
+        let _serial = TEST_SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+        let (entered_sender, entered) = channel();
+        let (release_sender, release) = channel::<()>();
+        let mut first = true;
+        let observer = monitor().observe(Box::new(move |_: &Snapshot| {
+            if std::mem::take(&mut first) {
+                let _ = entered_sender.send(());
+                let _ = release.recv();
+            }
+        }));
+
+        assert!(sampler().start());
+        entered.recv().unwrap();
+        // Die Aktualisierung hängt noch im Beobachter, `stop` wartet nicht darauf
+        let stopping = Instant::now();
+        assert!(sampler().stop());
+        assert!(stopping.elapsed() < Duration::from_secs(1));
+        assert!(!sampler().is_running());
+
+        drop(release_sender);
+        monitor().unobserve(observer);
+    }
+}
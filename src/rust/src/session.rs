@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use napi::bindgen_prelude::AsyncTask;
+use napi::threadsafe_function::{ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode, ErrorStrategy};
+use napi::{Env, Error, JsBoolean, JsFunction, JsObject, JsUnknown, Result as NapiResult, Status, ValueType};
+use once_cell::sync::Lazy;
+
+use crate::execution::{create_interpreter, prepare_execution, value_from_js, value_to_js, ExecuteTask, ExecutionOptions};
+use crate::language::{Interpreter, NativeSignature, ParameterKind, RuntimeError, TypeSet, Value};
+
+// Benannte Interpreter, deren globale Variablen und Funktionen zwischen den Läufen
+// erhalten bleiben (REPL im Code-Editor)
+static SESSIONS: Lazy<Mutex<HashMap<String, Arc<Mutex<Interpreter>>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn get_session(name: &str) -> NapiResult<Arc<Mutex<Interpreter>>> {
+    SESSIONS.lock().unwrap()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| Error::new(Status::InvalidArg, format!("Session '{}' does not exist", name)))
+}
+
+fn add_session(name: String, interpreter: Interpreter) -> NapiResult<()> {
+    let mut sessions = SESSIONS.lock().unwrap();
+    if sessions.contains_key(&name) {
+        return Err(Error::new(Status::InvalidArg, format!("Session '{}' already exists", name)));
+    }
+
+    sessions.insert(name, Arc::new(Mutex::new(interpreter)));
+    Ok(())
+}
+
+#[napi]
+pub fn create_session(
+    name: String,
+    callback: ThreadsafeFunction<String, ErrorStrategy::Fatal>,
+) -> napi::Result<()> {
+    add_session(name, create_interpreter(Some(callback)))
+}
+
+// Wie `execute_code`, aber im Interpreter der Session
+#[napi]
+pub fn run_in_session(
+    name: String,
+    code: String,
+    options: Option<ExecutionOptions>,
+) -> napi::Result<AsyncTask<ExecuteTask>> {
+    Ok(AsyncTask::new(session_task(&name, code, options)?))
+}
+
+fn session_task(name: &str, code: String, options: Option<ExecutionOptions>) -> NapiResult<ExecuteTask> {
+    prepare_execution(code, get_session(name)?, options)
+}
+
+// Globale Variablen der Session als Objekt. Funktionen erscheinen als Text,
+// damit sie in der Übersicht sichtbar bleiben.
+#[napi]
+pub fn get_session_globals(env: Env, name: String) -> napi::Result<JsObject> {
+    let session = get_session(&name)?;
+    let globals = match session.try_lock() {
+        Ok(interpreter) => interpreter.user_globals(),
+        Err(_) => return Err(Error::new(Status::GenericFailure, format!("Session '{}' is busy", name))),
+    };
+
+    let mut object = env.create_object()?;
+    for (name, value) in globals {
+        let js_value = match value {
+            Value::NativeFunction(_) | Value::Function(_) | Value::Closure(_) =>
+                env.create_string(&value.to_string())?.into_unknown(),
+            _ => value_to_js(&env, &value, 0)?,
+        };
+        object.set_named_property(&name, js_value)?;
+    }
+    Ok(object)
+}
+
+// Optionen für `register_session_function`. Ohne `params` nimmt die Funktion
+// beliebig viele Argumente entgegen.
+#[napi(object)]
+#[derive(Default)]
+pub struct ScriptFunctionOptions {
+    pub params: Option<Vec<String>>,
+    pub doc: Option<String>,
+}
+
+// Ruft `callback` auf, wartet ein zurückgegebenes Promise ab und meldet das Ergebnis über
+// `settle(ok, value)`. Der Aufruf läuft selbst in einem `then`, so erreicht auch ein
+// geworfener Fehler das Skript. Aufgerufen als `wrapper(callback, error, settle, ...args)`:
+// Ist `error` gesetzt, ließen sich die Argumente nicht umwandeln; das Skript hat den
+// Fehler dann bereits erhalten und die Funktion wird nicht aufgerufen.
+fn settle_wrapper(env: &Env, name: &str) -> NapiResult<JsFunction> {
+    env.create_function_from_closure(name, |call| {
+        if call.get::<JsUnknown>(1)?.get_type()? != ValueType::Null {
+            return call.env.get_undefined();
+        }
+        let mut args = vec![call.env.get_null()?.into_unknown()];
+        for i in 3..call.length {
+            args.push(call.get::<JsUnknown>(i)?);
+        }
+
+        let promise = call.env.get_global()?.get_named_property::<JsFunction>("Promise")?.coerce_to_object()?;
+        let started = promise
+            .get_named_property::<JsFunction>("resolve")?
+            .call_without_args(Some(&promise))?
+            .coerce_to_object()?;
+        let result = call_method(&started, "then", &[bind(call.get::<JsFunction>(0)?, args)?])?;
+        call_method(&result, "then", &[
+            bind(call.get::<JsFunction>(2)?, vec![call.env.get_null()?.into_unknown(), call.env.get_boolean(true)?.into_unknown()])?,
+            bind(call.get::<JsFunction>(2)?, vec![call.env.get_null()?.into_unknown(), call.env.get_boolean(false)?.into_unknown()])?,
+        ])?;
+        call.env.get_undefined()
+    })
+}
+
+// `function.bind(...args)`; das erste Argument ist `this`
+fn bind(function: JsFunction, args: Vec<JsUnknown>) -> NapiResult<JsUnknown> {
+    let object = function.coerce_to_object()?;
+    object.get_named_property::<JsFunction>("bind")?.call(Some(&object), &args)
+}
+
+fn call_method(object: &JsObject, name: &str, args: &[JsUnknown]) -> NapiResult<JsObject> {
+    object.get_named_property::<JsFunction>(name)?.call(Some(object), args)?.coerce_to_object()
+}
+
+// Text eines abgelehnten Promise: die `message` eines Errors, sonst der Wert selbst
+fn rejection_message(reason: JsUnknown) -> NapiResult<String> {
+    let reason = match reason.get_type()? {
+        ValueType::Object | ValueType::Function => {
+            let object = reason.coerce_to_object()?;
+            let message = object.get_named_property::<JsUnknown>("message")?;
+            if message.get_type()? == ValueType::Undefined { object.into_unknown() } else { message }
+        },
+        _ => reason,
+    };
+    reason.coerce_to_string()?.into_utf8()?.into_owned()
+}
+
+type Settlement = Result<Value, String>;
+
+// `settle` und die umgewandelten Argumente für einen Aufruf der JS-Funktion
+fn settle_arguments(env: &Env, args: &[Value], sender: Sender<Settlement>) -> NapiResult<Vec<JsUnknown>> {
+    let settle = env.create_function_from_closure("settle", move |call| {
+        let ok = call.get::<JsBoolean>(0)?.get_value()?;
+        let settlement = if ok {
+            value_from_js(call.get::<JsUnknown>(1)?, 0).map_err(|error| error.reason)
+        } else {
+            Err(rejection_message(call.get::<JsUnknown>(1)?)?)
+        };
+        // Der Empfänger fehlt nur, wenn das Skript inzwischen abgebrochen wurde
+        let _ = sender.send(settlement);
+        call.env.get_undefined()
+    })?;
+
+    let mut js_args = vec![settle.into_unknown()];
+    for arg in args {
+        js_args.push(value_to_js(env, arg, 0)?);
+    }
+    Ok(js_args)
+}
+
+// Eine JS-Funktion als native Funktion der Session. Das Skript blockiert, bis die
+// Funktion (bzw. ihr Promise) fertig ist; Abbruch und Zeitlimit des Laufs gelten weiter.
+#[napi]
+pub fn register_session_function(
+    env: Env,
+    session: String,
+    name: String,
+    callback: JsFunction,
+    options: Option<ScriptFunctionOptions>,
+) -> napi::Result<()> {
+    let interpreter = get_session(&session)?;
+    let mut interpreter = interpreter
+        .try_lock()
+        .map_err(|_| Error::new(Status::GenericFailure, format!("Session '{}' is busy", session)))?;
+
+    let wrapper = bind(settle_wrapper(&env, &name)?, vec![env.get_null()?.into_unknown(), callback.into_unknown()])?;
+    let wrapper = JsFunction::try_from(wrapper)?;
+
+    // Mit `Fatal` würde jeder Fehler beim Umwandeln (z.B. ein zyklisches Argument) den
+    // ganzen Prozess beenden. Hier geht er stattdessen als Ergebnis an das Skript.
+    let mut tsfn: ThreadsafeFunction<(Vec<Value>, Sender<Settlement>)> = wrapper
+        .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<(Vec<Value>, Sender<Settlement>)>| {
+            let (args, sender) = ctx.value;
+            settle_arguments(&ctx.env, &args, sender.clone()).inspect_err(|error| {
+                let _ = sender.send(Err(error.reason.clone()));
+            })
+        })?;
+    // Registrierte Funktionen sollen Node nicht am Beenden hindern
+    tsfn.unref(&env)?;
+
+    let options = options.unwrap_or_default();
+    let signature = match options.params {
+        Some(params) => params.iter().fold(NativeSignature::new(&name), |signature, param| signature.param(param, TypeSet::ANY)),
+        None => NativeSignature::new(&name).variadic("args", TypeSet::ANY),
+    };
+    let signature = signature
+        .returns(TypeSet::ANY)
+        .doc(options.doc.as_deref().unwrap_or_default());
+
+    let function_name = name.clone();
+    interpreter.register_native(signature, Arc::new(move |interpreter: &Interpreter, args: Vec<Value>| {
+        let (sender, receiver) = channel();
+        let status = tsfn.call(Ok((args, sender.clone())), ThreadsafeFunctionCallMode::NonBlocking);
+        if status != Status::Ok {
+            return Err(RuntimeError::Custom(format!("Cannot call '{}': {}", function_name, status)));
+        }
+
+        await_settlement(interpreter, &receiver, &sender, &function_name)
+    }));
+    Ok(())
+}
+
+// Wartet auf das Ergebnis einer registrierten JS-Funktion. Ein Abbruch weckt das Skript
+// über denselben Kanal, das Zeitlimit über die Wartezeit.
+fn await_settlement(
+    interpreter: &Interpreter,
+    receiver: &Receiver<Settlement>,
+    sender: &Sender<Settlement>,
+    name: &str,
+) -> Result<Value, RuntimeError> {
+    let _listener = interpreter.limits().cancellation.as_ref().map(|token| {
+        let sender = sender.clone();
+        token.on_cancel(move || {
+            let _ = sender.send(Err("cancelled".to_string()));
+        })
+    });
+
+    loop {
+        let settlement = match interpreter.remaining_time() {
+            Some(remaining) => receiver.recv_timeout(remaining),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        // Abbruch und Zeitlimit gehen vor, auch wenn gleichzeitig ein Ergebnis eintrifft
+        interpreter.check_interrupted()?;
+        match settlement {
+            Ok(Ok(value)) => return Ok(value),
+            Ok(Err(message)) => return Err(RuntimeError::Custom(format!("{}: {}", name, message))),
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(RuntimeError::Custom(format!("'{}' did not return a result", name)));
+            },
+        }
+    }
+}
+
+// Ein noch laufendes Skript der Session läuft zu Ende, danach wird der Interpreter freigegeben
+#[napi]
+pub fn drop_session(name: String) -> bool {
+    SESSIONS.lock().unwrap().remove(&name).is_some()
+}
+
+// Signaturen aller nativen Funktionen als JSON, für Vervollständigung und Signaturhilfe
+// im Editor. Mit Session-Namen einschließlich der dort registrierten Funktionen.
+#[napi]
+pub fn get_builtin_signatures(session: Option<String>) -> napi::Result<String> {
+    let signatures: Vec<serde_json::Value> = match session {
+        Some(name) => {
+            let session = get_session(&name)?;
+            let interpreter = session
+                .try_lock()
+                .map_err(|_| Error::new(Status::GenericFailure, format!("Session '{}' is busy", name)))?;
+            interpreter.native_signatures().into_iter().map(signature_json).collect()
+        },
+        None => create_interpreter(None).native_signatures().into_iter().map(signature_json).collect(),
+    };
+    Ok(serde_json::Value::Array(signatures).to_string())
+}
+
+fn signature_json(signature: &NativeSignature) -> serde_json::Value {
+    let params: Vec<serde_json::Value> = signature.params
+        .iter()
+        .map(|param| serde_json::json!({
+            "name": param.name,
+            "type": param.types.to_string(),
+            "optional": param.kind == ParameterKind::Optional,
+            "variadic": param.kind == ParameterKind::Variadic,
+        }))
+        .collect();
+
+    serde_json::json!({
+        "name": signature.name,
+        "signature": signature.to_string(),
+        "params": params,
+        "returns": signature.returns.to_string(),
+        "doc": signature.doc,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use napi::Task;
+    use crate::execution::{cancel_execution, ExecutionOutcome};
+    use crate::language::{Backend, InterruptReason};
+    use std::time::Duration;
+
+    fn new_session(name: &str) {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_backend(Backend::Bytecode);
+        add_session(name.to_string(), interpreter).unwrap();
+    }
+
+    fn run(name: &str, code: &str) -> NapiResult<ExecutionOutcome> {
+        session_task(name, code.to_string(), None)?.compute()
+    }
+
+    #[test]
+    fn test_globals_persist_across_runs() {
+        new_session("test-persist");
+        assert!(matches!(run("test-persist", "fn double(x) { return x * 2; } var base = 4;"), Ok(ExecutionOutcome::Completed(_))));
+        match run("test-persist", "double(base) + 1;") {
+            Ok(ExecutionOutcome::Completed(value)) => assert_eq!(value, Value::Number(9.0)),
+            _ => panic!("second run should see the first run's globals"),
+        }
+        assert!(drop_session("test-persist".to_string()));
+    }
+
+    #[test]
+    fn test_drop_session_invalidates_name() {
+        new_session("test-drop");
+        assert!(drop_session("test-drop".to_string()));
+        assert!(matches!(run("test-drop", "1;"), Err(error) if error.reason.contains("does not exist")));
+        assert!(!drop_session("test-drop".to_string()));
+
+        // Der Name ist danach wieder frei
+        new_session("test-drop");
+        assert!(drop_session("test-drop".to_string()));
+    }
+
+    #[test]
+    fn test_concurrent_run_is_busy() {
+        new_session("test-busy");
+        let session = get_session("test-busy").unwrap();
+        let running = session.lock().unwrap();
+        assert!(matches!(run("test-busy", "1;"), Err(error) if error.reason.contains("busy")));
+        drop(running);
+        assert!(matches!(run("test-busy", "1;"), Ok(ExecutionOutcome::Completed(_))));
+        assert!(drop_session("test-busy".to_string()));
+    }
+
+    #[test]
+    fn test_unsettled_function_is_cancelled() {
+        new_session("test-unsettled");
+        {
+            let session = get_session("test-unsettled").unwrap();
+            let mut interpreter = session.lock().unwrap();
+            // Wie eine registrierte JS-Funktion, deren Promise nie erfüllt wird
+            interpreter.register_native(NativeSignature::new("hang").returns(TypeSet::ANY), Arc::new(|interpreter: &Interpreter, _| {
+                let (sender, receiver) = channel();
+                await_settlement(interpreter, &receiver, &sender, "hang")
+            }));
+        }
+
+        let options = ExecutionOptions { id: Some("test-unsettled".to_string()), ..Default::default() };
+        let mut task = session_task("test-unsettled", "hang();".to_string(), Some(options)).unwrap();
+        let canceller = std::thread::spawn(|| {
+            std::thread::sleep(Duration::from_millis(50));
+            cancel_execution("test-unsettled".to_string())
+        });
+        match task.compute() {
+            Ok(ExecutionOutcome::Failed(error)) => {
+                assert!(matches!(error.error, RuntimeError::Interrupted { reason: InterruptReason::Cancelled, .. }));
+            },
+            _ => panic!("the waiting function should be interrupted"),
+        }
+        assert!(canceller.join().unwrap());
+        assert!(drop_session("test-unsettled".to_string()));
+    }
+}